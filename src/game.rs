@@ -1,22 +1,70 @@
-use std::{borrow::BorrowMut, fs, fs::File, io::Write, ops::Deref};
+use std::{borrow::BorrowMut, fs, ops::Deref};
 
 use fs::read_to_string;
+use faction::character::character_trait::Deed;
+use rand::{rngs::StdRng, SeedableRng};
 use screen::TextureElement;
 
 use crate::engine::{self, event::Listener};
 
 use super::Config;
 
+mod ai;
+mod battle;
+mod crash;
+mod diplomacy;
+mod event;
 mod faction;
 mod map;
+mod queue;
+mod rebellion;
+mod resources;
+mod replay;
+mod rules;
+mod save;
 mod screen;
 mod listener;
+mod siege;
+mod supply;
+mod trade;
+mod turn;
+mod validate;
+mod victory;
+mod world;
+
+/* Text file stem on the button of events without choices */
+const EVENT_CONTINUE_TEXT: &str = "event_continue";
+/* Save slot a running campaign is kept in when the game is closed */
+pub const EXIT_SLOT: &str = "exit";
+/* Save slot of the campaign that was running when the game crashed */
+const EMERGENCY_SLOT: &str = "emergency";
+/* Save slots the load screen has room for */
+const LISTED_SAVES: usize = 5;
 
 pub struct Game {
     /* Remove Option when JSON loading is implemented */
     factions: Option<Vec<faction::Faction>>,
     player_faction: Option<faction::Faction>,
     map: Option<map::Map>,
+    rules: Option<rules::Rules>,
+    diplomacy: Option<diplomacy::Diplomacy>,
+    sieges: Vec<siege::Siege>,
+    turn_manager: turn::TurnManager,
+    /* Set once the campaign is won or lost, no further turns are played */
+    outcome: Option<victory::Outcome>,
+    /* Campaign seed, every random outcome is derived from it */
+    seed: u64,
+    /* What happened to the player during the turn, printed once the next turn starts */
+    report: Vec<String>,
+    /* What happened during the turn that scripted events may be triggered by */
+    happenings: Vec<event::Happening>,
+    /* Ids of the events that fired this campaign */
+    fired_events: Vec<String>,
+    /* Ids of the events still waiting for the player to make a choice, oldest first */
+    pending_events: Vec<String>,
+    autosave: save::AutosaveSettings,
+    /* Every command given since the campaign started, see replay */
+    command_log: replay::CommandLog,
     paths: Config,
     pub close: bool,
     screens: Vec<screen::Screen>,
@@ -49,6 +97,18 @@ impl Game {
             factions: None,
             player_faction: None,
             map: None,
+            rules: None,
+            diplomacy: None,
+            sieges: Vec::new(),
+            turn_manager: turn::TurnManager::new(),
+            outcome: None,
+            seed: 0,
+            report: Vec::new(),
+            happenings: Vec::new(),
+            fired_events: Vec::new(),
+            pending_events: Vec::new(),
+            autosave: save::AutosaveSettings::load(&paths.resource_manager.get_config("game.json")),
+            command_log: replay::CommandLog::default(),
             paths: paths,
             close: false,
             screens,
@@ -68,20 +128,945 @@ impl Game {
         }
     }
 
-    pub fn load_world(&self, player_faction_name: &str) {
+    pub fn load_world(&mut self, player_faction_name: &str) -> Result<(), world::WorldError> {
+        self.start_campaign(player_faction_name, rand::random())
+    }
+
+    /* Starts a campaign from the world files. The same faction and seed always lead to the same
+    campaign, given the same commands. */
+    fn start_campaign(&mut self, player_faction_name: &str, seed: u64) -> Result<(), world::WorldError> {
         println!("Starting game, player has chosen: {}", player_faction_name);
-        for faction_file in self.paths.resource_manager.get_world_data("factions") {
-            let faction_file_content = match fs::read_to_string(faction_file) {
-                Ok(f) => f,
-                Err(e) => panic!("Could not open faction file because of: {}", e),
+        let world = world::World::load(&self.paths.resource_manager, player_faction_name)?;
+
+        let mut factions = world.factions;
+        factions.push(faction::Faction::rebels());
+        self.factions = Some(factions);
+        self.player_faction = Some(world.player_faction);
+        self.map = Some(world.map);
+        self.rules = Some(world.rules);
+        self.diplomacy = Some(world.diplomacy);
+        self.sieges = Vec::new();
+        self.update_visibility();
+        self.update_blockades();
+        self.turn_manager = turn::TurnManager::new();
+        self.outcome = None;
+        self.seed = seed;
+        self.command_log = replay::CommandLog::new(player_faction_name, seed);
+        self.report = Vec::new();
+        self.happenings = Vec::new();
+        self.fired_events = Vec::new();
+        self.pending_events = Vec::new();
+        self.appoint_characters();
+
+        /* The first turn starts with the income phase and then waits for the player */
+        self.run_phase(turn::Phase::Income);
+        self.turn_manager.next_phase();
+        self.fire_events();
+        self.record_state_hash();
+        Ok(())
+    }
+
+    fn record_state_hash(&mut self) {
+        let state = match (&self.factions, &self.player_faction, &self.map, &self.diplomacy) {
+            (Some(f), Some(p), Some(m), Some(d)) => replay::State { factions: f, player_faction: p, map: m, diplomacy: d, sieges: &self.sieges, turn_manager: &self.turn_manager },
+            _ => return
+        };
+        let hash = state.hash();
+        self.command_log.record_hash(self.turn_manager.get_turn(), hash);
+    }
+
+    /* Writes the command log of the running campaign into the replay directory */
+    fn write_command_log(&self) {
+        if self.player_faction.is_none() {
+            return;
+        }
+        if let Err(e) = self.command_log.write(&self.paths.resource_manager.get_replays()) {
+            println!("Could not write the command log: {}", e);
+        }
+    }
+
+    /* Plays the command log in the file again from the starting world without a window. Returns
+    false if the replay does not reach the recorded state on every turn. */
+    pub fn replay(paths: Config, file: &str) -> bool {
+        let log = match replay::CommandLog::read(file) {
+            Ok(l) => l,
+            Err(e) => {
+                println!("{}", e);
+                return false;
+            }
+        };
+
+        let replayed = match Game::play_log(paths, &log) {
+            Ok(r) => r,
+            Err(e) => {
+                println!("Could not load world: {}", e);
+                return false;
+            }
+        };
+
+        let turns = log.get_hashes().len().min(replayed.get_hashes().len());
+        match log.first_divergence(&replayed) {
+            None => {
+                println!("The replay of {} matches the recorded campaign on all {} turns", file, turns);
+                true
+            }
+            Some(turn) => {
+                println!("The replay of {} diverges from the recorded campaign at the start of turn {}", file, turn);
+                let previous = turn.saturating_sub(1);
+                match log.first_difference(&replayed, previous) {
+                    Some((recorded, replayed)) => println!("First differing command of turn {}: recorded {:?}, replayed {:?}", previous, recorded, replayed),
+                    None => println!("The commands of turn {} are the same, the rules or the world files have changed since the recording", previous),
+                }
+                false
+            }
+        }
+    }
+
+    /* Plays the commands of the player in the log from the starting world and returns the log of the replay */
+    fn play_log(paths: Config, log: &replay::CommandLog) -> Result<replay::CommandLog, world::WorldError> {
+        let mut game = Game::new(paths, (0.0, 0.0));
+        game.autosave = save::AutosaveSettings::none();
+        game.start_campaign(log.get_player_faction(), log.get_seed())?;
+        for c in log.player_commands() {
+            game.run_command(&c.code);
+        }
+        Ok(game.command_log)
+    }
+
+    /* Checks the screens, render units, texts and the world files and prints every problem. Returns
+    false if there is any. */
+    pub fn validate(paths: &Config) -> bool {
+        let problems = validate::run(&paths.resource_manager);
+        for p in problems.iter() {
+            println!("{}", p);
+        }
+        match problems.len() {
+            0 => println!("No problems found"),
+            n => println!("Found {} problems", n),
+        }
+        problems.is_empty()
+    }
+
+    /* Carries out a command of the player, whether it was clicked or comes from a replay */
+    fn run_command(&mut self, code: &str) {
+        let (map, rules, diplomacy, player_faction) = match (&mut self.map, &self.rules, &mut self.diplomacy, &mut self.player_faction) {
+            (Some(m), Some(r), Some(d), Some(p)) => (m, r, d, p),
+            _ => return
+        };
+        self.command_log.record(self.turn_manager.get_turn(), player_faction.get_id(), code);
+
+        if code == "end_turn" {
+            self.end_turn();
+            return;
+        }
+        if let Some(index) = code.strip_prefix("choose ") {
+            self.choose(index.parse().expect("choose command used without a valid choice."));
+            return;
+        }
+
+        map.retreive_event_code(code);
+        diplomacy.retreive_event_code(code, player_faction.get_id());
+        for siege in self.sieges.iter_mut() {
+            siege.retreive_event_code(code, player_faction.get_id());
+        }
+        player_faction.retreive_event_code(code, map, rules, diplomacy);
+    }
+
+    /* Writes the running campaign into the save slot, there is nothing to save outside a campaign */
+    pub fn save_game(&self, slot: &str) -> Result<(), save::SaveError> {
+        let (factions, player_faction, map, diplomacy) = match (&self.factions, &self.player_faction, &self.map, &self.diplomacy) {
+            (Some(f), Some(p), Some(m), Some(d)) => (f, p, m, d),
+            _ => return Ok(())
+        };
+
+        let metadata = save::Metadata::new(slot, self.turn_manager.get_turn(), player_faction.get_id());
+        let save = save::Save::new(
+            metadata,
+            save::Campaign {
+                factions: factions.clone(),
+                player_faction: player_faction.clone(),
+                map: map.clone(),
+                diplomacy: diplomacy.clone(),
+                sieges: self.sieges.clone(),
+                turn_manager: self.turn_manager.clone(),
+                outcome: self.outcome,
+                seed: self.seed,
+                report: self.report.clone(),
+                happenings: self.happenings.clone(),
+                fired_events: self.fired_events.clone(),
+                pending_events: self.pending_events.clone(),
+                command_log: self.command_log.clone(),
+            },
+        );
+        save::write(&self.paths.resource_manager.get_saves(), &save)?;
+        println!("Saved turn {} of {} in {}", save.metadata.turn, save.metadata.faction, slot);
+        Ok(())
+    }
+
+    /* Restores the campaign in the save slot. The rules come from the data path as they are now. */
+    pub fn load_game(&mut self, slot: &str) -> Result<(), save::SaveError> {
+        let save = save::read(&self.paths.resource_manager.get_saves(), slot)?;
+        let rules = world::World::load_rules(&self.paths.resource_manager).map_err(save::SaveError::World)?;
+        save::check(&save, &rules)?;
+        println!("Loading turn {} of {} from {}", save.metadata.turn, save.metadata.faction, slot);
+
+        let campaign = save.campaign;
+        self.factions = Some(campaign.factions);
+        self.player_faction = Some(campaign.player_faction);
+        self.map = Some(campaign.map);
+        self.rules = Some(rules);
+        self.diplomacy = Some(campaign.diplomacy);
+        self.sieges = campaign.sieges;
+        self.turn_manager = campaign.turn_manager;
+        self.outcome = campaign.outcome;
+        self.seed = campaign.seed;
+        self.report = campaign.report;
+        self.happenings = campaign.happenings;
+        self.fired_events = campaign.fired_events;
+        self.pending_events = campaign.pending_events;
+        self.command_log = campaign.command_log;
+        self.report_sightings();
+        Ok(())
+    }
+
+    /* Fills the load screen with the newest saves, "load_slot <index>" loads one of them */
+    fn list_saves(&mut self, engine: &mut engine::Engine) {
+        let saves = save::list(&self.paths.resource_manager.get_saves());
+        let screen = match self.open_screens.last_mut() {
+            Some(s) => s,
+            None => return
+        };
+        for (i, s) in saves.iter().take(LISTED_SAVES).enumerate() {
+            screen.render_event_string(engine, &i.to_string(), &format!("{} - {}, turn {}", s.slot, s.faction, s.turn));
+            screen.render_event_texture(engine, &i.to_string());
+        }
+    }
+
+    /* Runs the remaining phases of the current turn and the income phase of the next one, then
+    hands control back to the player. */
+    pub fn end_turn(&mut self) {
+        if self.player_faction.is_none() || self.outcome.is_some() || self.turn_manager.get_phase() != turn::Phase::PlayerOrders {
+            return;
+        }
+
+        loop {
+            let phase = self.turn_manager.next_phase();
+            if phase == turn::Phase::PlayerOrders {
+                break;
+            }
+            self.run_phase(phase);
+            if self.outcome.is_some() {
+                return;
+            }
+        }
+        println!("Turn {} has started", self.turn_manager.get_turn());
+        for line in self.report.drain(..) {
+            println!("{}", line);
+        }
+        self.report_sightings();
+        self.fire_events();
+        self.record_state_hash();
+
+        if let Some(slot) = self.autosave.slot(self.turn_manager.get_turn()) {
+            if let Err(e) = self.save_game(&slot) {
+                println!("Could not autosave the campaign: {}", e);
+            }
+        }
+    }
+
+    /* Keeps what the panic was about until it has unwound to the main loop, see write_crash_report */
+    pub fn install_panic_hook() {
+        crash::install_hook();
+    }
+
+    /* Writes the running campaign into the emergency slot and a crash report next to the saves */
+    pub fn write_crash_report(&self) {
+        crash::write_report(self, EMERGENCY_SLOT);
+    }
+
+    /* Events triggered by the last turn or by the start of this one fire for the player. They wait
+    in pending_events until the player has made a choice. */
+    fn fire_events(&mut self) {
+        let (map, rules, diplomacy, player) = match (&self.map, &self.rules, &self.diplomacy, &self.player_faction) {
+            (Some(m), Some(r), Some(d), Some(p)) => (m, r, d, p),
+            _ => return
+        };
+        let context = event::Context {
+            map,
+            diplomacy,
+            factions: self.factions.iter().flatten().chain(std::iter::once(player)).collect(),
+            player: player.get_id(),
+            turn: self.turn_manager.get_turn(),
+            happenings: &self.happenings,
+        };
+
+        for e in rules.get_events() {
+            let fired = self.fired_events.iter().any(|f| f == e.get_id());
+            if (fired && !e.is_repeatable()) || !e.is_triggered(&context) || !e.conditions_hold(&context) {
+                continue;
+            }
+            if !fired {
+                self.fired_events.push(e.get_id().to_string());
+            }
+            self.pending_events.push(e.get_id().to_string());
+        }
+        self.happenings.clear();
+    }
+
+    /* Carries out the choice the player made for the oldest pending event */
+    fn choose(&mut self, index: usize) {
+        let id = match self.pending_events.first() {
+            Some(i) => i.clone(),
+            None => return
+        };
+        let effects: Vec<event::Effect> = match self.rules.as_ref().and_then(|r| r.get_event(&id)) {
+            /* Events without choices only have the button to go on */
+            Some(e) if e.get_choices().is_empty() && index == 0 => Vec::new(),
+            Some(e) => match e.get_choices().get(index) {
+                Some(c) => c.effects.clone(),
+                None => {
+                    println!("The event {} has no choice {}", id, index);
+                    return;
+                }
+            },
+            None => Vec::new(),
+        };
+
+        self.pending_events.remove(0);
+        for effect in effects.iter() {
+            self.apply_effect(effect);
+        }
+        self.update_visibility();
+    }
+
+    fn apply_effect(&mut self, effect: &event::Effect) {
+        let (map, rules, diplomacy) = match (&mut self.map, &self.rules, &mut self.diplomacy) {
+            (Some(m), Some(r), Some(d)) => (m, r, d),
+            _ => return
+        };
+        let player = match &self.player_faction {
+            Some(p) => p.get_id().to_string(),
+            None => return
+        };
+        let mut factions: Vec<&mut faction::Faction> = self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()).collect();
+
+        match effect {
+            event::Effect::Treasury { faction, resources } => {
+                if let Some(f) = factions.iter_mut().find(|f| f.get_id() == event::resolve(faction, &player)) {
+                    f.change_treasury(*resources);
+                }
+            }
+            event::Effect::Opinion { from, to, value } => diplomacy.add_modifier(event::resolve(from, &player), event::resolve(to, &player), "Recent events", *value),
+            event::Effect::Relation { faction, other, state } => diplomacy.set_state(event::resolve(faction, &player), event::resolve(other, &player), *state),
+            event::Effect::AddUnits { faction, region, unit_type, count } => {
+                let (unit_type, region) = match (rules.get_unit_type(unit_type), map.get_region(region)) {
+                    (Some(u), Some(r)) => (u, r),
+                    _ => return
+                };
+                if let Some(f) = factions.iter_mut().find(|f| f.get_id() == event::resolve(faction, &player)) {
+                    let units = (0..*count).map(|_| faction::army::unit::Unit::new(unit_type)).collect();
+                    f.reinforce(&format!("{} Levy", region.get_name()), region.get_id(), region.get_position(), units);
+                }
+            }
+            event::Effect::SetOwner { region, faction } => {
+                let taker = event::resolve(faction, &player).to_string();
+                transfer_region(map, &mut factions, region, &taker);
+                self.happenings.push(event::Happening::RegionOwnerChanged { region: region.clone(), owner: taker });
+            }
+        }
+    }
+
+    /* Opens the event screen for the oldest pending event. Its text goes into the first event text
+    element, the choices into the following ones next to their buttons. */
+    fn show_event(&mut self, engine: &mut engine::Engine, paths: &Config) {
+        let event = match (self.pending_events.first(), &self.rules) {
+            (Some(id), Some(r)) => match r.get_event(id) {
+                Some(e) => e.clone(),
+                None => return
+            },
+            _ => return
+        };
+
+        self.open_screen("event", engine);
+        let screen = match self.open_screens.last_mut() {
+            Some(s) if s.name == "event" => s,
+            _ => panic!("There is no event screen")
+        };
+        screen.render_event_text(engine, "0", Some(event.get_text()), paths);
+        let choices: Vec<&str> = match event.get_choices() {
+            [] => vec![EVENT_CONTINUE_TEXT],
+            c => c.iter().map(|c| c.text.as_str()).collect(),
+        };
+        for (i, text) in choices.into_iter().enumerate() {
+            screen.render_event_text(engine, &(i + 1).to_string(), Some(text), paths);
+            screen.render_event_texture(engine, &i.to_string());
+        }
+    }
+
+    /* Fills every office the world files left empty */
+    fn appoint_characters(&mut self) {
+        let rules = match &self.rules {
+            Some(r) => r,
+            None => return
+        };
+        for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()) {
+            let mut rng = StdRng::seed_from_u64(battle::battle_seed(self.seed, 0, &format!("{} characters", f.get_id())));
+            f.appoint_characters(rules, &mut rng);
+        }
+    }
+
+    /* Only what happens to the characters of the player makes it into the turn report */
+    fn characters_turn(&mut self) {
+        let rules = match &self.rules {
+            Some(r) => r,
+            None => return
+        };
+        let (turn, season) = (self.turn_manager.get_turn(), self.turn_manager.get_season());
+        for f in self.factions.iter_mut().flatten() {
+            let mut rng = StdRng::seed_from_u64(battle::battle_seed(self.seed, turn, &format!("{} characters", f.get_id())));
+            f.characters_turn(rules, season, &mut rng);
+        }
+        if let Some(p) = &mut self.player_faction {
+            let mut rng = StdRng::seed_from_u64(battle::battle_seed(self.seed, turn, &format!("{} characters", p.get_id())));
+            self.report.extend(p.characters_turn(rules, season, &mut rng));
+        }
+    }
+
+    /* Tells the player about traits their generals earned */
+    fn report_traits(&mut self, rewarded: Vec<(String, String, String)>) {
+        let player = match &self.player_faction {
+            Some(p) => p.get_id().to_string(),
+            None => return
+        };
+        for (faction, general, t) in rewarded.into_iter().filter(|(f, _, _)| *f == player) {
+            self.report.push(format!("{} of {} is now {}", general, faction, t));
+        }
+    }
+
+    fn update_visibility(&mut self) {
+        if let Some(map) = &self.map {
+            for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()) {
+                f.update_visibility(map);
+            }
+        }
+    }
+
+    /* Foreign armies the player can see, armies under the fog of war stay hidden */
+    fn report_sightings(&self) {
+        let player = match &self.player_faction {
+            Some(p) => p,
+            None => return
+        };
+
+        for f in self.factions.iter().flatten() {
+            for a in f.get_armies().iter().filter(|a| player.can_see(a.get_region())) {
+                match a.get_general() {
+                    Some(g) => println!("Spotted {} of {} in {} with {} men led by {}", a.get_name(), f.get_id(), a.get_region(), a.get_men(), g.get_name()),
+                    None => println!("Spotted {} of {} in {} with {} men", a.get_name(), f.get_id(), a.get_region(), a.get_men()),
+                }
+            }
+            for fl in f.get_fleets().iter().filter(|fl| player.can_see(fl.get_region())) {
+                println!("Spotted {} of {} in {} with {} ships", fl.get_name(), f.get_id(), fl.get_region(), fl.get_ships());
+            }
+        }
+    }
+
+    /* Finds the trade routes of every faction before the income phase pays them out. The player
+    hears about every route and what it brings. */
+    fn update_trade(&mut self) {
+        let (map, rules, diplomacy) = match (&self.map, &self.rules, &self.diplomacy) {
+            (Some(m), Some(r), Some(d)) => (m, r, d),
+            _ => return
+        };
+        let all: Vec<&faction::Faction> = self.factions.iter().flatten().chain(self.player_faction.iter()).collect();
+        let armies: Vec<(String, String)> = all
+            .iter()
+            .flat_map(|f| f.get_armies().iter().map(move |a| (f.get_id().to_string(), a.get_region().to_string())))
+            .collect();
+        let routes: Vec<(String, Vec<trade::TradeRoute>)> = all.iter().map(|f| (f.get_id().to_string(), trade::find_routes(f, &all, map, rules, diplomacy, &armies))).collect();
+
+        for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()) {
+            if let Some((_, r)) = routes.iter().find(|(id, _)| id == f.get_id()) {
+                f.set_trade_routes(r.clone());
+            }
+        }
+
+        if let Some(p) = &self.player_faction {
+            for r in p.get_trade_routes() {
+                match &r.disrupted_by {
+                    Some(enemy) => self.report.push(format!("The trade route from {} to {} of {} is cut by {}", r.from, r.to, r.partner, enemy)),
+                    None => self.report.push(format!("The trade route from {} to {} of {} brings {} gold", r.from, r.to, r.partner, r.income)),
+                }
+            }
+        }
+    }
+
+    fn run_phase(&mut self, phase: turn::Phase) {
+        if phase == turn::Phase::Income {
+            self.update_trade();
+        }
+        let (map, rules, diplomacy) = match (&self.map, &self.rules, &mut self.diplomacy) {
+            (Some(m), Some(r), Some(d)) => (m, r, d),
+            _ => return
+        };
+
+        match phase {
+            /* Only the non player factions are run by the AI */
+            turn::Phase::PlayerOrders => {
+                if let Some(f) = &mut self.player_faction {
+                    f.run_phase(phase, map, rules, diplomacy);
+                }
+            }
+            turn::Phase::AiFactions => {
+                let armies: Vec<(String, String, f32)> = self
+                    .factions
+                    .iter()
+                    .flatten()
+                    .chain(self.player_faction.iter())
+                    .flat_map(|f| f.get_armies().iter().map(move |a| (f.get_id().to_string(), a.get_region().to_string(), a.power(rules))))
+                    .collect();
+
+                let fleets: Vec<(String, String, u32)> = self
+                    .factions
+                    .iter()
+                    .flatten()
+                    .chain(self.player_faction.iter())
+                    .flat_map(|f| f.get_fleets().iter().map(move |fl| (f.get_id().to_string(), fl.get_region().to_string(), fl.get_ships())))
+                    .collect();
+
+                let mut orders: Vec<(String, String)> = Vec::new();
+                for f in self.factions.iter_mut().flatten() {
+                    /* The AI only knows about armies and fleets outside the fog of war */
+                    let visible: Vec<(String, String, f32)> = armies.iter().filter(|(o, r, _)| o == f.get_id() || f.can_see(r)).cloned().collect();
+                    let visible_fleets: Vec<(String, String, u32)> = fleets.iter().filter(|(o, r, _)| o == f.get_id() || f.can_see(r)).cloned().collect();
+                    let context = ai::Context { map, rules, diplomacy, armies: &visible, fleets: &visible_fleets, sieges: &self.sieges };
+                    let mut ai = ai::WeightedAi::new(f.get_ai_weights().clone());
+                    for o in f.ai_phase(&mut ai, &context) {
+                        orders.push((f.get_id().to_string(), o));
+                    }
+                }
+
+                let turn = self.turn_manager.get_turn();
+                for (faction, order) in orders {
+                    self.command_log.record(turn, &faction, &order);
+                    diplomacy.retreive_event_code(&order, &faction);
+                    for s in self.sieges.iter_mut() {
+                        s.retreive_event_code(&order, &faction);
+                    }
+                }
+                if let Some(p) = &self.player_faction {
+                    diplomacy.answer_ai_proposals(p.get_id());
+                }
+            }
+            _ => {
+                for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()) {
+                    f.run_phase(phase, map, rules, diplomacy);
+                }
+            }
+        }
+
+        match phase {
+            turn::Phase::Income => {
+                if let Some(p) = &self.player_faction {
+                    let treasury = p.get_treasury();
+                    if treasury.gold < 0 || treasury.food < 0 {
+                        self.report.push(format!("The treasury is in debt: {} gold and {} food", treasury.gold, treasury.food));
+                    }
+                }
+            }
+            turn::Phase::Battles => {
+                self.resolve_naval_battles();
+                self.update_blockades();
+                self.resolve_battles();
+                self.resolve_sieges();
+                /* Retreats and captures change what can be seen */
+                self.update_visibility();
+            }
+            turn::Phase::EndOfTurn => {
+                let season = self.turn_manager.get_season();
+                for f in self.factions.iter_mut().flatten() {
+                    f.suffer_attrition(map, diplomacy, season);
+                }
+                if let Some(p) = &mut self.player_faction {
+                    for l in p.suffer_attrition(map, diplomacy, season) {
+                        if l.perished {
+                            self.report.push(format!("{} perished in {} out of supply", l.army, l.region));
+                        } else {
+                            self.report.push(format!("{} lost {} men to attrition in {}", l.army, l.men, l.region));
+                        }
+                    }
+                }
+                diplomacy.end_turn();
+                self.characters_turn();
+                self.spread_rebellion();
+                self.check_campaign_end();
+            }
+            _ => {}
+        }
+    }
+
+    /* Settlements whose public order broke down raise rebel armies in their region, which then try
+    to take the settlement like any enemy. A region only rises again once its rebels are gone. The
+    player hears about unrest in their own settlements before it comes to that. */
+    fn spread_rebellion(&mut self) {
+        let (map, rules) = match (&self.map, &self.rules) {
+            (Some(m), Some(r)) => (m, r),
+            _ => return
+        };
+        let occupied: Vec<String> = match self.factions.iter().flatten().find(|f| f.is_rebel()) {
+            Some(r) => r.get_armies().iter().map(|a| a.get_region().to_string()).collect(),
+            None => return,
+        };
+
+        let mut risings: Vec<rebellion::Rising> = Vec::new();
+        for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()).filter(|f| !f.is_rebel()) {
+            let names: Vec<String> = f.get_settlements().iter().filter(|s| s.is_rebellious()).map(|s| s.get_name().to_string()).collect();
+            for name in names {
+                let region = match map.get_regions().iter().find(|r| r.get_settlement() == Some(name.as_str())) {
+                    Some(r) if !occupied.iter().any(|o| o == r.get_id()) => r,
+                    _ => continue,
+                };
+                let settlement = f.get_settlement_mut(&name).expect("Settlement was just listed");
+                if let Some(units) = rebellion::rise(settlement, rules) {
+                    risings.push(rebellion::Rising {
+                        settlement: name,
+                        owner: f.get_id().to_string(),
+                        region: region.get_id().to_string(),
+                        position: region.get_position(),
+                        units,
+                    });
+                }
+            }
+        }
+
+        if let Some(p) = &self.player_faction {
+            for s in p.get_settlements().iter().filter(|s| !s.is_rebellious() && s.get_public_order() < faction::settlement::UNREST_ORDER) {
+                self.report.push(format!("The people of {} are restless, public order is {}", s.get_name(), s.get_public_order()));
+            }
+        }
+
+        let rebels = match self.factions.iter_mut().flatten().find(|f| f.is_rebel()) {
+            Some(r) => r,
+            None => return,
+        };
+        for r in risings {
+            self.report.push(format!("The people of {} have risen against {}", r.settlement, r.owner));
+            rebels.raise_army(&format!("Rebels of {}", r.settlement), &r.region, r.position, r.units);
+        }
+    }
+
+    /* Drops defeated factions and decides whether the campaign is over for the player */
+    fn check_campaign_end(&mut self) {
+        let (map, player) = match (&self.map, &self.player_faction) {
+            (Some(m), Some(p)) => (m, p),
+            _ => return
+        };
+        let turn = self.turn_manager.get_turn();
+
+        /* The rebels are never defeated for good and never win, the next rising brings them back */
+        if let Some(factions) = &mut self.factions {
+            for f in factions.iter().filter(|f| !f.is_rebel() && victory::is_defeated(f)) {
+                println!("{} has been defeated", f.get_id());
+                self.happenings.push(event::Happening::FactionEliminated { faction: f.get_id().to_string() });
+            }
+            factions.retain(|f| f.is_rebel() || !victory::is_defeated(f));
+        }
+
+        let all: Vec<&faction::Faction> = self.factions.iter().flatten().filter(|f| !f.is_rebel()).chain(std::iter::once(player)).collect();
+        let winner = all.iter().find(|f| victory::has_won(f, &all, map, turn));
+
+        self.outcome = if victory::is_defeated(player) {
+            Some(victory::Outcome::Defeat)
+        } else if victory::has_won(player, &all, map, turn) || all.len() == 1 {
+            Some(victory::Outcome::Victory)
+        } else if let Some(w) = winner {
+            println!("{} has won the campaign", w.get_id());
+            Some(victory::Outcome::Defeat)
+        } else {
+            None
+        };
+    }
+
+    /* Fleets of factions at war that lie in the same region fight like armies do, see resolve_battles.
+    Armies aboard sunk ships drown. */
+    fn resolve_naval_battles(&mut self) {
+        let (map, diplomacy) = match (&self.map, &mut self.diplomacy) {
+            (Some(m), Some(d)) => (m, d),
+            _ => return
+        };
+        let mut factions: Vec<&mut faction::Faction> = self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()).collect();
+
+        for region in map.get_regions() {
+            let mut sides: Vec<battle::NavalSide> = Vec::new();
+            for f in factions.iter_mut() {
+                let fleets = f.take_fleets_in(region.get_id());
+                if fleets.is_empty() {
+                    continue;
+                }
+
+                /* In a port the owner's ships come first */
+                let side = battle::NavalSide::new(f.get_id().to_string(), fleets);
+                if region.get_owner() == Some(f.get_id()) {
+                    sides.insert(0, side);
+                } else {
+                    sides.push(side);
+                }
+            }
+
+            /* Factions on neither side, such as neutrals passing through, stay out of the battle */
+            let present: Vec<&str> = sides.iter().map(|s| s.faction.as_str()).collect();
+            let coalition = battle::coalitions(&present, diplomacy);
+            let (fighting, bystanders): (Vec<_>, Vec<_>) = sides.into_iter().zip(coalition).partition(|(_, c)| c.is_some());
+            let (mut sides, coalition): (Vec<battle::NavalSide>, Vec<usize>) = fighting.into_iter().map(|(s, c)| (s, c.unwrap_or(0))).unzip();
+            let bystanders: Vec<battle::NavalSide> = bystanders.into_iter().map(|(s, _)| s).collect();
+
+            if coalition.contains(&1) {
+                for (attacker, _) in sides.iter().zip(coalition.iter()).filter(|(_, &c)| c == 1) {
+                    diplomacy.battle_fought(&sides[0].faction, &attacker.faction);
+                }
+
+                let seed = battle::battle_seed(self.seed, self.turn_manager.get_turn(), &format!("{} naval", region.get_id()));
+                let result = battle::resolve_naval(&mut sides, &coalition, seed);
+                println!("Naval battle of {}: {} wins, ships sunk {:?}, men drowned {:?}", region.get_id(), sides[result.winner].faction, result.sunk, result.drowned);
+
+                for &i in &result.retreating {
+                    battle::retreat_fleets(&mut sides[i], region, map, diplomacy);
+                }
+            }
+
+            for side in sides.into_iter().chain(bystanders) {
+                if let Some(f) = factions.iter_mut().find(|f| f.get_id() == side.faction) {
+                    f.return_fleets(side.fleets);
+                }
+            }
+        }
+    }
+
+    fn update_blockades(&mut self) {
+        let (map, diplomacy) = match (&self.map, &self.diplomacy) {
+            (Some(m), Some(d)) => (m, d),
+            _ => return
+        };
+
+        let fleets: Vec<(String, String)> = self
+            .factions
+            .iter()
+            .flatten()
+            .chain(self.player_faction.iter())
+            .flat_map(|f| f.get_fleets().iter().map(move |fl| (f.get_id().to_string(), fl.get_region().to_string())))
+            .collect();
+        for f in self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()) {
+            f.update_blockades(map, diplomacy, &fleets);
+        }
+    }
+
+    /* Every region with armies of factions at war is fought over. The first side, usually the region
+    owner, and its allies fight everyone at war with it. The losers retreat to a neighbouring region they may
+    enter, or surrender if there is none. Factions at peace that meet cause a border incident. */
+    fn resolve_battles(&mut self) {
+        let (map, rules, diplomacy) = match (&self.map, &self.rules, &mut self.diplomacy) {
+            (Some(m), Some(r), Some(d)) => (m, r, d),
+            _ => return
+        };
+        let mut factions: Vec<&mut faction::Faction> = self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()).collect();
+        let mut rewarded: Vec<(String, String, String)> = Vec::new();
+
+        for region in map.get_regions() {
+            let mut sides: Vec<battle::BattleSide> = Vec::new();
+            for f in factions.iter_mut() {
+                let armies = f.take_armies_in(region.get_id());
+                if armies.is_empty() {
+                    continue;
+                }
+
+                /* The region owner always comes first, it wins battles nobody could win */
+                let defender = region.get_owner() == Some(f.get_id());
+                let side = battle::BattleSide::new(f.get_id().to_string(), armies, defender, 0.0);
+                if defender {
+                    sides.insert(0, side);
+                } else {
+                    sides.push(side);
+                }
+            }
+
+            /* Factions on neither side, such as neutrals passing through, stay out of the battle */
+            let present: Vec<&str> = sides.iter().map(|s| s.faction.as_str()).collect();
+            let coalition = battle::coalitions(&present, diplomacy);
+            let (fighting, bystanders): (Vec<_>, Vec<_>) = sides.into_iter().zip(coalition).partition(|(_, c)| c.is_some());
+            let (mut sides, coalition): (Vec<battle::BattleSide>, Vec<usize>) = fighting.into_iter().map(|(s, c)| (s, c.unwrap_or(0))).unzip();
+            let bystanders: Vec<battle::BattleSide> = bystanders.into_iter().map(|(s, _)| s).collect();
+
+            let present: Vec<&str> = sides.iter().chain(bystanders.iter()).map(|s| s.faction.as_str()).collect();
+            for (i, a) in present.iter().enumerate() {
+                for b in present.iter().skip(i + 1) {
+                    if !diplomacy.at_war(a, b) {
+                        diplomacy.border_incident(a, b);
+                    }
+                }
+            }
+
+            if coalition.contains(&1) {
+                for (attacker, _) in sides.iter().zip(coalition.iter()).filter(|(_, &c)| c == 1) {
+                    diplomacy.battle_fought(&sides[0].faction, &attacker.faction);
+                }
+
+                let landscape = map.get_landscape(region.get_landscape()).expect("Region without landscape");
+                let seed = battle::battle_seed(self.seed, self.turn_manager.get_turn(), region.get_id());
+                let result = battle::resolve(&mut sides, &coalition, landscape, rules, seed);
+                println!("Battle of {}: {} wins, casualties {:?}, morale {:?}, destroyed {:?}", region.get_id(), sides[result.winner].faction, result.casualties, result.morale_changes, result.destroyed);
+
+                rewarded.extend(battle::reward_generals(&mut sides, &result.retreating, rules, seed));
+                self.happenings.push(event::Happening::BattleWon { faction: sides[result.winner].faction.clone(), region: region.get_id().to_string() });
+
+                for &i in &result.retreating {
+                    battle::retreat(&mut sides[i], region, map, diplomacy);
+                }
+            }
+
+            for side in sides.into_iter().chain(bystanders) {
+                if let Some(f) = factions.iter_mut().find(|f| f.get_id() == side.faction) {
+                    f.return_armies(side.armies);
+                }
+            }
+        }
+        self.report_traits(rewarded);
+    }
+
+    /* A settlement whose owner has no army left in its region is besieged by the enemy armies there.
+    Without a garrison it falls at once, otherwise it holds out until its supplies run out, the
+    walls are stormed or the garrison breaks out. Regions without a settlement are simply taken. */
+    fn resolve_sieges(&mut self) {
+        let (map, rules, diplomacy) = match (&self.map, &self.rules, &self.diplomacy) {
+            (Some(m), Some(r), Some(d)) => (m, r, d),
+            _ => return
+        };
+        let mut factions: Vec<&mut faction::Faction> = self.factions.iter_mut().flatten().chain(self.player_faction.iter_mut()).collect();
+        let turn = self.turn_manager.get_turn();
+        /* Region ids with the faction that takes them */
+        let mut captures: Vec<(String, String)> = Vec::new();
+        let mut besieged: Vec<String> = Vec::new();
+        let mut rewarded: Vec<(String, String, String)> = Vec::new();
+
+        for region in map.get_regions() {
+            let id = region.get_id();
+            let owner = match factions.iter().position(|f| region.get_owner() == Some(f.get_id())) {
+                Some(o) if !factions[o].has_armies_in(id) => o,
+                _ => continue,
             };
+            let besieger = match factions.iter().position(|f| f.has_armies_in(id) && diplomacy.at_war(factions[owner].get_id(), f.get_id())) {
+                Some(b) => b,
+                None => continue,
+            };
+            let besieger_id = factions[besieger].get_id().to_string();
 
-            let faction_file_content: &str = &faction_file_content[..];
+            let settlement = match region.get_settlement().and_then(|s| factions[owner].get_settlement_mut(s)) {
+                Some(s) if s.get_garrison_men() > 0 => s,
+                _ => {
+                    captures.push((id.to_string(), besieger_id));
+                    continue;
+                }
+            };
 
-            let _: faction::Faction = match serde_json::from_str(faction_file_content) {
-                Ok(f) => f,
-                Err(e) => panic!("Could not create faction from JSON: {}", e),
+            let siege = match self.sieges.iter().position(|s| s.get_region() == id && s.get_besieger() == besieger_id) {
+                Some(i) => &mut self.sieges[i],
+                None => {
+                    println!("{} lays siege to {}", besieger_id, settlement.get_name());
+                    self.sieges.retain(|s| s.get_region() != id);
+                    self.sieges.push(siege::Siege::new(id.to_string(), besieger_id.clone(), region.get_owner().unwrap_or("").to_string()));
+                    self.sieges.last_mut().unwrap()
+                }
             };
+            siege.update_garrison(settlement.garrison_power(rules), settlement.fortification(rules));
+
+            let action = siege.get_action();
+            let mut lifted = false;
+            if action == siege::Action::Wait {
+                settlement.besieged_turn();
+            } else {
+                /* Stormed walls help the garrison, a sally leaves them behind */
+                let assault = action == siege::Action::Assault;
+                let wall_bonus = if assault { siege.get_wall_bonus() } else { 0.0 };
+                let garrison = settlement.take_garrison();
+                let defender = factions[owner].get_id().to_string();
+                let mut sides = vec![
+                    battle::BattleSide::new(defender, vec![garrison], assault, wall_bonus),
+                    battle::BattleSide::new(besieger_id.clone(), factions[besieger].take_armies_in(id), false, 0.0),
+                ];
+
+                let landscape = map.get_landscape(region.get_landscape()).expect("Region without landscape");
+                let seed = battle::battle_seed(self.seed, turn, &format!("{} siege", id));
+                /* Garrison and besiegers are always enemies */
+                let result = battle::resolve(&mut sides, &[0, 1], landscape, rules, seed);
+                println!("{:?} at {}: {} wins, casualties {:?}", action, id, sides[result.winner].faction, result.casualties);
+                rewarded.extend(battle::reward_generals(&mut sides, &result.retreating, rules, seed));
+                self.happenings.push(event::Happening::BattleWon { faction: sides[result.winner].faction.clone(), region: id.to_string() });
+
+                let mut besiegers = sides.pop().unwrap();
+                let mut garrison = sides.pop().unwrap();
+                if result.winner == 0 {
+                    battle::retreat(&mut besiegers, region, map, diplomacy);
+                    lifted = true;
+                }
+                factions[besieger].return_armies(besiegers.armies);
+
+                let settlement = factions[owner].get_settlement_mut(region.get_settlement().unwrap()).unwrap();
+                if let Some(g) = garrison.armies.pop() {
+                    settlement.return_garrison(g);
+                }
+                /* Storming the walls ends the siege either way */
+                if assault && result.winner == 1 {
+                    captures.push((id.to_string(), besieger_id));
+                    continue;
+                }
+            }
+
+            let settlement = factions[owner].get_settlement_mut(region.get_settlement().unwrap()).unwrap();
+            if settlement.get_garrison_men() == 0 {
+                captures.push((id.to_string(), besieger_id));
+            } else if lifted {
+                println!("The siege of {} has been lifted", settlement.get_name());
+            } else {
+                siege.advance();
+                besieged.push(id.to_string());
+            }
+        }
+
+        self.sieges.retain(|s| besieged.iter().any(|b| b == s.get_region()));
+        for f in factions.iter_mut() {
+            f.resupply_settlements(map, &besieged);
+        }
+
+        let map = match &mut self.map {
+            Some(m) => m,
+            None => return
+        };
+        for (id, taker) in captures {
+            let settlement_taken = transfer_region(map, &mut factions, &id, &taker);
+            self.happenings.push(event::Happening::RegionOwnerChanged { region: id.clone(), owner: taker.clone() });
+            match factions.iter_mut().find(|f| f.get_id() == taker) {
+                Some(f) if settlement_taken => {
+                    let mut rng = StdRng::seed_from_u64(battle::battle_seed(self.seed, turn, &format!("{} capture", id)));
+                    for (general, t) in f.reward_generals(&id, Deed::SettlementCaptured, rules, &mut rng) {
+                        rewarded.push((taker.clone(), general, t));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.report_traits(rewarded);
+    }
+
+    /* The screen on top, whose button sent a "render texture <i>" or "render text <i>" code, with
+    the kind of element and its index. None if that screen has no such element. */
+    fn render_target<'a>(&'a mut self, code: &'a str) -> Option<(&'a mut screen::Screen, &'a str, usize)> {
+        let mut words = code.split(' ').skip(1);
+        let (kind, index) = (words.next()?, words.next()?.parse::<usize>().ok()?);
+        let screen = self.open_screens.last_mut()?;
+        let (texts, textures) = screen.get_event_element_counts();
+        match kind {
+            "texture" if index < textures => Some((screen, kind, index)),
+            "text" if index < texts => Some((screen, kind, index)),
+            _ => None
         }
     }
 
@@ -102,68 +1087,92 @@ impl Game {
                     Some(i) => i,
                     None => panic!("Critical error occurred!")
                 }.close();
+                continue;
             }
 
             if s.starts_with("open") {
                 let split_string = s.split(" ");
                 self.open_screen(split_string.last().expect("No screen to open specified"), engine);
+                continue;
             }
 
             if s.starts_with("render") {
-                let mut split_string1 = s.split(" ");
-                let mut split_string2 = s.split(" ");
-                match split_string1.find(|&x| x == "texture") {
-                    Some(_) => {self.screens.last_mut().unwrap().render_event_texture(engine, split_string1.last().unwrap()); continue;},
-                    None => {}
-                };
-                match split_string2.find(|&x| x == "text") {
-                    Some(_) => {self.screens.last_mut().unwrap().render_event_text(engine, split_string2.last().unwrap(), paths); continue;},
-                    None => panic!("Neither texture nor text specified")
+                match self.render_target(s) {
+                    Some((screen, "texture", index)) => screen.render_event_texture(engine, &index.to_string()),
+                    Some((screen, _, index)) => screen.render_event_text(engine, &index.to_string(), None, paths),
+                    None => println!("Ignoring \"{}\", the open screen has no such element", s)
                 }
+                continue;
             }
 
             if s.starts_with("set") {
                 let split_string = s.split(" ");
                 self.event_string = split_string.last().expect("set command used without parameters.").to_string();
+                continue;
             }
 
-            if s.starts_with("play") {
-                if self.event_string == "" {panic!("Used play command without setting the player faction.")}
-                self.load_world(&self.event_string);
+            if s == "end_turn" {
+                let was_over = self.outcome.is_some();
+                self.run_command(s);
+                self.write_command_log();
+                if let (false, Some(o)) = (was_over, self.outcome) {
+                    self.open_screen(o.get_screen(), engine);
+                }
+                continue;
+            }
+
+            if let Some(slot) = s.strip_prefix("save ") {
+                if let Err(e) = self.save_game(slot) {
+                    println!("Could not save the campaign: {}", e);
+                }
+                continue;
             }
 
-            match &mut self.map {
-                Some(i) => i,
-                None => continue
-            }.retreive_event_code(&s[..]);
+            if s == "list_saves" {
+                self.list_saves(engine);
+                continue;
+            }
 
-            match &mut self.player_faction {
-                Some(i) => i,
-                None => continue
-            }.retreive_event_code(&s[..]);
-        }
-        self.listener.event_codes.clear();
-    }
+            if let Some(index) = s.strip_prefix("load_slot ") {
+                let index: usize = index.parse().expect("load_slot command used without a valid index.");
+                let slot = match save::list(&self.paths.resource_manager.get_saves()).get(index) {
+                    Some(s) => s.slot.clone(),
+                    None => continue
+                };
+                match self.load_game(&slot) {
+                    Ok(_) => self.open_screen("campaign", engine),
+                    Err(e) => println!("Could not load the campaign: {}", e),
+                }
+                continue;
+            }
 
-    pub fn save_world(&self) {
-        let file =
-            File::create(self.paths.resource_manager.get_world().to_owned() + "test_faction.json");
-        let f = faction::Faction::new("Hello there".to_string());
-        let json_data = serde_json::to_string(&f);
+            if s.starts_with("choose") {
+                self.run_command(s);
+                if self.pending_events.is_empty() {
+                    self.open_screen("campaign", engine);
+                } else {
+                    self.show_event(engine, paths);
+                }
+                continue;
+            }
 
-        let mut save_file = match file {
-            Ok(f) => f,
-            Err(e) => panic!("Could not create save file while saving world: {}", e),
-        };
+            if s.starts_with("play") {
+                if self.event_string == "" {panic!("Used play command without setting the player faction.")}
+                let player_faction_name = self.event_string.clone();
+                if let Err(e) = self.load_world(&player_faction_name) {
+                    panic!("Could not load world: {}", e);
+                }
+                continue;
+            }
 
-        let save_data = match json_data {
-            Ok(s) => s,
-            Err(e) => panic!("Could not proceed JSON data while saving world: {}", e),
-        };
+            self.run_command(s);
+        }
+        self.listener.event_codes.clear();
 
-        match save_file.write_all(save_data.as_bytes()) {
-            Ok(_) => (),
-            Err(e) => panic!("Error while saving world: {}", e),
+        /* Events fired by the new turn or the start of the campaign wait on top of the campaign screen */
+        let showing_event = self.open_screens.last().is_some_and(|s| s.name == "event");
+        if !self.pending_events.is_empty() && !showing_event && self.outcome.is_none() {
+            self.show_event(engine, paths);
         }
     }
 
@@ -173,6 +1182,32 @@ impl Game {
 }
 
 
+/* Hands the region to the taker along with its settlement, which surrenders to its new owner.
+Returns true if there was a settlement to take. */
+fn transfer_region(map: &mut map::Map, factions: &mut [&mut faction::Faction], id: &str, taker: &str) -> bool {
+    let region = match map.get_region_mut(id) {
+        Some(r) => r,
+        None => return false,
+    };
+    let previous = region.get_owner().map(|o| o.to_string());
+    region.set_owner(Some(taker.to_string()));
+
+    let name = match region.get_settlement() {
+        Some(n) => n,
+        None => {
+            println!("{} has occupied {}", taker, id);
+            return false;
+        }
+    };
+    let settlement = factions.iter_mut().find(|f| previous.as_deref() == Some(f.get_id())).and_then(|f| f.take_settlement(name));
+    if let (Some(mut s), Some(f)) = (settlement, factions.iter_mut().find(|f| f.get_id() == taker)) {
+        s.surrender();
+        f.add_settlement(s);
+    }
+    println!("{} has taken {}", taker, name);
+    true
+}
+
 impl Listener for Game {
     fn key_pressed(&mut self) {
     
@@ -189,4 +1224,48 @@ impl Listener for Game {
     fn window_closed(&mut self) {
         self.close = true;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource_manager::ResourceManager;
+
+    fn game() -> Game {
+        let mut resource_manager = ResourceManager::new("assets/", "config/", "data/");
+        resource_manager.set_world("assets/world/");
+        Game::new(Config { resource_manager }, (1000.0, 1000.0))
+    }
+
+    #[test]
+    fn render_codes_go_to_the_screen_on_top() {
+        let mut game = game();
+        /* Like open_screen, without the window */
+        let faction_selection = game.screens.iter().find(|s| s.name == "faction_selection").unwrap().clone();
+        game.open_screens.push(faction_selection.clone());
+
+        let codes: Vec<&str> = faction_selection.get_event_codes().into_iter().filter(|c| c.starts_with("render")).collect();
+        assert!(codes.contains(&"render texture 22"));
+        for code in codes {
+            let (screen, _, _) = game.render_target(code).unwrap_or_else(|| panic!("{} has no target", code));
+            assert_eq!(screen.name, "faction_selection");
+        }
+        assert!(game.render_target("render texture 25").is_none());
+        assert!(game.render_target("render text x").is_none());
+    }
+
+    #[test]
+    fn replay_reaches_the_recorded_state_every_turn() {
+        let mut game = game();
+        game.autosave = save::AutosaveSettings::none();
+        game.start_campaign("arthedain", 7).unwrap();
+        for _ in 0..3 {
+            game.run_command("end_turn");
+        }
+        let recorded = game.command_log.clone();
+
+        let replayed = Game::play_log(game.paths.clone(), &recorded).unwrap();
+        assert_eq!(recorded.get_hashes().len(), 4);
+        assert_eq!(replayed.get_hashes(), recorded.get_hashes());
+        assert_eq!(recorded.first_divergence(&replayed), None);
+    }
+}
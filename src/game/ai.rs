@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::{Diplomacy, State};
+use super::faction::{army::unit::unit_type::UnitType, settlement::{self, Tax}, Faction, REBELS};
+use super::map::Map;
+use super::rules::Rules;
+use super::siege::Siege;
+use super::supply;
+
+/* Targets further away than this are not worth marching to */
+const MAX_TARGET_COST: u32 = 30;
+/* Share of the treasury the AI keeps back for bad times */
+const RESERVE: f32 = 0.25;
+/* Public order above which the AI dares to raise taxes */
+const CONTENT_ORDER: i32 = 70;
+
+/* Tunes how a faction's AI plays, 1.0 is the default for every weight. Set per faction in the
+"ai" entry of its file. */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Weights {
+    /* How weak a target has to be before it is attacked and how eagerly wars are declared */
+    pub aggression: f32,
+    /* Share of the spendable gold that goes into buildings */
+    pub economy: f32,
+    /* Share of the spendable gold that goes into new units */
+    pub military: f32,
+    /* How readily treaties are offered */
+    pub diplomacy: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            aggression: 1.0,
+            economy: 1.0,
+            military: 1.0,
+            diplomacy: 1.0,
+        }
+    }
+}
+
+/* Everything an AI may look at besides its own faction. Nothing in here needs the Engine. */
+pub struct Context<'a> {
+    pub map: &'a Map,
+    pub rules: &'a Rules,
+    pub diplomacy: &'a Diplomacy,
+    /* Faction id, region id and power of every army on the map */
+    pub armies: &'a [(String, String, f32)],
+    /* Faction id, region id and ships of every fleet */
+    pub fleets: &'a [(String, String, u32)],
+    pub sieges: &'a [Siege],
+}
+
+impl Context<'_> {
+    fn hostile_power_in(&self, region: &str, faction: &str) -> f32 {
+        self.armies.iter().filter(|(f, r, _)| r == region && self.diplomacy.at_war(faction, f)).map(|(_, _, p)| p).sum()
+    }
+
+    fn own_power_in(&self, region: &str, faction: &str) -> f32 {
+        self.armies.iter().filter(|(f, r, _)| r == region && f == faction).map(|(_, _, p)| p).sum()
+    }
+
+    fn hostile_ships_in(&self, region: &str, faction: &str) -> u32 {
+        self.fleets.iter().filter(|(f, r, _)| r == region && self.diplomacy.at_war(faction, f)).map(|(_, _, s)| s).sum()
+    }
+
+    fn total_power(&self, faction: &str) -> f32 {
+        self.armies.iter().filter(|(f, _, _)| f == faction).map(|(_, _, p)| p).sum()
+    }
+
+    /* Factions owning a region next to one of the faction's regions, there is no talking to rebels */
+    fn neighbours(&self, faction: &str) -> Vec<String> {
+        let mut neighbours: Vec<String> = Vec::new();
+        for region in self.map.get_regions().iter().filter(|r| r.get_owner() == Some(faction)) {
+            for n in region.get_land_neighbours() {
+                if let Some(o) = self.map.get_region(n).and_then(|r| r.get_owner()) {
+                    if o != faction && o != REBELS && !neighbours.iter().any(|x| x == o) {
+                        neighbours.push(o.to_string());
+                    }
+                }
+            }
+        }
+        neighbours
+    }
+}
+
+/* Decides what a non player faction does with its turn. The orders are the same event codes the
+player sends, so they go through the same checks. */
+pub trait Ai {
+    fn plan_turn(&mut self, faction: &Faction, context: &Context) -> Vec<String>;
+}
+
+/* The default AI, it greedily picks the best option for every decision by its weights */
+pub struct WeightedAi {
+    weights: Weights,
+}
+
+impl WeightedAi {
+    pub fn new(weights: Weights) -> Self {
+        WeightedAi { weights }
+    }
+
+    fn plan_diplomacy(&self, faction: &Faction, context: &Context, orders: &mut Vec<String>) {
+        if faction.is_rebel() {
+            return;
+        }
+        let id = faction.get_id();
+        let own_power = context.total_power(id);
+
+        for other in context.neighbours(id) {
+            let state = context.diplomacy.get_state(id, &other);
+            let opinion = context.diplomacy.get_opinion(id, &other) as f32;
+            let other_power = context.total_power(&other);
+
+            match state {
+                /* Losing wars are ended, winning ones go on */
+                State::War if own_power * self.weights.aggression < other_power => orders.push(format!("propose peace {}", other)),
+                State::Peace if opinion < 0.0 && own_power * self.weights.aggression > other_power * 2.0 => {
+                    orders.push(format!("declare_war {}", other));
+                    /* One new war at a time */
+                    return;
+                }
+                State::Peace if opinion * self.weights.diplomacy >= 0.0 => orders.push(format!("propose non_aggression {}", other)),
+                State::NonAggression if opinion * self.weights.diplomacy >= 40.0 => orders.push(format!("propose alliance {}", other)),
+                _ => {}
+            }
+
+            /* Economy minded factions trade even with factions they dislike */
+            if state != State::War && !context.diplomacy.has_trade_agreement(id, &other) && opinion + 20.0 * self.weights.economy >= 20.0 {
+                orders.push(format!("propose trade {}", other));
+            }
+        }
+    }
+
+    fn plan_construction(&self, faction: &Faction, context: &Context, budget: &mut i32, orders: &mut Vec<String>) {
+        for s in faction.get_settlements() {
+            if !s.get_construction().is_empty() {
+                continue;
+            }
+
+            /* Cheapest building first, anything is better than an idle settlement */
+            let building = context
+                .rules
+                .get_buildings()
+                .iter()
+                .filter(|b| s.construction_blocker(b, faction.get_culture()).is_none() && b.get_cost().gold <= *budget)
+                .min_by_key(|b| b.get_cost().gold);
+
+            if let Some(b) = building {
+                *budget -= b.get_cost().gold;
+                orders.push(format!("build {} {}", b.get_id(), s.get_name()));
+            }
+        }
+    }
+
+    fn plan_recruitment(&self, faction: &Faction, context: &Context, budget: &mut i32, orders: &mut Vec<String>) {
+        /* New units have to be paid for every turn as well */
+        let income = faction.income(context.rules) - faction.upkeep(context.rules);
+
+        for s in faction.get_settlements() {
+            if !s.get_recruitment().is_empty() {
+                continue;
+            }
+
+            /* Most power for the gold that can still be paid for */
+            let unit_type = context
+                .rules
+                .get_unit_types()
+                .iter()
+                .filter(|u| u.get_cost().gold <= *budget && u.get_upkeep().gold < income.gold && u.get_upkeep().food < income.food)
+                .filter(|u| faction.recruitment_blocker(u, s.get_name(), context.map).is_none())
+                .max_by(|a, b| {
+                    let value = |u: &&UnitType| (u.get_men() * (u.get_attack() + u.get_defence())) as f32 / u.get_cost().gold.max(1) as f32;
+                    value(a).total_cmp(&value(b))
+                });
+
+            if let Some(u) = unit_type {
+                *budget -= u.get_cost().gold;
+                orders.push(format!("recruit {} {}", u.get_id(), s.get_name()));
+            }
+        }
+    }
+
+    /* Taxes are lowered where the people are restless and raised one step at a time where they
+    are content */
+    fn plan_taxes(&self, faction: &Faction, orders: &mut Vec<String>) {
+        for s in faction.get_settlements() {
+            let order = s.get_public_order();
+            let tax = match s.get_tax() {
+                _ if order < settlement::UNREST_ORDER => Tax::Low,
+                Tax::Low if order > CONTENT_ORDER => Tax::Normal,
+                Tax::Normal if order > CONTENT_ORDER + 10 => Tax::High,
+                tax => tax,
+            };
+            if s.get_tax() != tax {
+                orders.push(format!("tax {} {}", tax.get_name(), s.get_name()));
+            }
+        }
+    }
+
+    /* Fleets blockade the closest enemy port whose waters they can hold */
+    fn plan_fleets(&self, faction: &Faction, context: &Context, orders: &mut Vec<String>) {
+        let id = faction.get_id();
+
+        for fleet in faction.get_fleets() {
+            let mut best: Option<(u32, &str)> = None;
+            for port in context.map.get_regions().iter().filter(|r| context.map.is_port(r)) {
+                if !port.get_owner().is_some_and(|o| context.diplomacy.at_war(id, o)) {
+                    continue;
+                }
+
+                for sea in context.map.get_sea_regions(port) {
+                    if context.hostile_ships_in(sea.get_id(), id) as f32 >= fleet.get_ships() as f32 * self.weights.aggression {
+                        continue;
+                    }
+                    let path = match context.map.find_sea_path(fleet.get_region(), sea.get_id(), |r| context.diplomacy.may_enter(id, r)) {
+                        Some(p) => p,
+                        None => continue,
+                    };
+                    if best.is_none_or(|(c, _)| path.cost < c) {
+                        best = Some((path.cost, sea.get_id()));
+                    }
+                }
+            }
+
+            if let Some((_, target)) = best {
+                orders.push(format!("sail {} {}", target, fleet.get_name()));
+            }
+        }
+    }
+
+    /* Walls are stormed and garrisons break out once they are strong enough to win */
+    fn plan_sieges(&self, faction: &Faction, context: &Context, orders: &mut Vec<String>) {
+        let id = faction.get_id();
+        for s in context.sieges {
+            let region = s.get_region();
+            if s.get_besieger() == id {
+                let power = context.own_power_in(region, id) * self.weights.aggression;
+                if power > s.get_garrison_power() * (1.0 + s.get_wall_bonus()) {
+                    orders.push(format!("assault {}", region));
+                }
+            } else if s.get_defender() == id && s.get_garrison_power() * self.weights.aggression > context.hostile_power_in(region, id) {
+                orders.push(format!("sally {}", region));
+            }
+        }
+    }
+
+    /* Every army marches on the most promising region of an enemy it can beat, or stays put */
+    fn plan_movement(&self, faction: &Faction, context: &Context, orders: &mut Vec<String>) {
+        let id = faction.get_id();
+
+        for army in faction.get_armies() {
+            let power = army.power(context.rules);
+            if power <= 0.0 {
+                continue;
+            }
+
+            let mut best: Option<(f32, &str)> = None;
+            for region in context.map.get_regions() {
+                if !region.get_owner().is_some_and(|o| context.diplomacy.at_war(id, o)) {
+                    continue;
+                }
+
+                let enemy_power = context.hostile_power_in(region.get_id(), id);
+                if power * self.weights.aggression <= enemy_power {
+                    continue;
+                }
+
+                let path = match context.map.find_path(army.get_region(), region.get_id(), |r| context.diplomacy.may_enter(id, r)) {
+                    Some(p) if p.cost <= MAX_TARGET_COST => p,
+                    _ => continue,
+                };
+
+                /* Settlements are worth more than open land, close targets more than far ones and
+                targets the army can be fed in more than those beyond the supply lines */
+                let mut value = if region.get_settlement().is_some() { 2.0 } else { 1.0 };
+                if !supply::is_supplied(context.map, context.diplomacy, id, region.get_id()) {
+                    value *= 0.5;
+                }
+                let score = value * (power * self.weights.aggression - enemy_power) / (path.cost + 1) as f32;
+                if best.is_none_or(|(s, _)| score > s) {
+                    best = Some((score, region.get_id()));
+                }
+            }
+
+            if let Some((_, target)) = best {
+                orders.push(format!("move {} {}", target, army.get_name()));
+            }
+        }
+    }
+}
+
+impl Ai for WeightedAi {
+    fn plan_turn(&mut self, faction: &Faction, context: &Context) -> Vec<String> {
+        let mut orders: Vec<String> = Vec::new();
+        self.plan_diplomacy(faction, context, &mut orders);
+        self.plan_taxes(faction, &mut orders);
+
+        /* The spendable gold is split between buildings and units by the weights */
+        let spendable = (faction.get_treasury().gold as f32 * (1.0 - RESERVE)).max(0.0);
+        let total = (self.weights.economy + self.weights.military).max(f32::EPSILON);
+        let mut construction_budget = (spendable * self.weights.economy / total) as i32;
+        let mut recruitment_budget = (spendable * self.weights.military / total) as i32;
+
+        self.plan_construction(faction, context, &mut construction_budget, &mut orders);
+        self.plan_recruitment(faction, context, &mut recruitment_budget, &mut orders);
+        self.plan_movement(faction, context, &mut orders);
+        self.plan_fleets(faction, context, &mut orders);
+        self.plan_sieges(faction, context, &mut orders);
+        orders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::game::faction::army::{unit::Unit, Army};
+    use crate::game::faction::settlement::Settlement;
+    use crate::game::queue::Queue;
+    use crate::game::resources::Resources;
+
+    fn unit_type(id: &str, gold: i32) -> UnitType {
+        let mut unit_type: UnitType = serde_json::from_value(json!({
+            "name": id, "men": 100, "attack": 3, "defence": 5, "health": 2, "morale": 60, "speed": 6,
+            "cost": {"gold": gold, "food": 0, "manpower": 25}, "upkeep": {"gold": 8, "food": 5, "manpower": 0}, "turns": 1
+        }))
+        .unwrap();
+        unit_type.set_id(id.to_string());
+        unit_type
+    }
+
+    fn rules() -> Rules {
+        Rules::new(vec![unit_type("spearmen", 80), unit_type("knights", 400)], Vec::new(), Vec::new(), Vec::new(), Vec::new())
+    }
+
+    /* "home" of a borders "border" and "keep" of b, "keep" lies behind "border" */
+    fn map() -> Map {
+        let region = |id: &str, owner: &str, neighbours: &[&str]| {
+            json!({"id": id, "name": id, "owner": owner, "landscape": "plains", "settlement": null, "position": [0.0, 0.0], "land_neighbours": neighbours, "sea_neighbours": []})
+        };
+        serde_json::from_value(json!({
+            "landscapes": [{"name": "plains", "movement_cost": 2, "passable": true, "defence_bonus": 0.0}],
+            "regions": [region("home", "a", &["border", "keep"]), region("border", "b", &["home", "keep"]), region("keep", "b", &["home", "border"])]
+        }))
+        .unwrap()
+    }
+
+    fn faction(id: &str, armies: Vec<Army>, settlements: Vec<Settlement>, gold: i32) -> Faction {
+        let treasury = Resources { gold, food: 100, manpower: 100 };
+        Faction::from_world(id.to_string(), id.to_string(), String::new(), armies, settlements, Vec::new(), treasury)
+    }
+
+    fn army(name: &str, region: &str, units: usize, rules: &Rules) -> Army {
+        let units = (0..units).map(|_| Unit::new(rules.get_unit_type("spearmen").unwrap())).collect();
+        Army::new(name.to_string(), (0.0, 0.0), region.to_string(), units, Queue::new())
+    }
+
+    fn plan(faction: &Faction, weights: Weights, map: &Map, rules: &Rules, diplomacy: &Diplomacy, armies: &[(String, String, f32)]) -> Vec<String> {
+        let context = Context { map, rules, diplomacy, armies, fleets: &[], sieges: &[] };
+        WeightedAi::new(weights).plan_turn(faction, &context)
+    }
+
+    #[test]
+    fn declares_war_on_a_weak_neighbour() {
+        let (map, rules) = (map(), rules());
+        let a = faction("a", Vec::new(), Vec::new(), 0);
+        let mut diplomacy = Diplomacy::new(vec!["a".to_string(), "b".to_string()]);
+        diplomacy.add_modifier("a", "b", "Border incident", -5);
+
+        let armies = [("a".to_string(), "home".to_string(), 100.0), ("b".to_string(), "keep".to_string(), 40.0)];
+        assert!(plan(&a, Weights::default(), &map, &rules, &diplomacy, &armies).contains(&"declare_war b".to_string()));
+
+        /* Not while the neighbour could hold its own */
+        let armies = [("a".to_string(), "home".to_string(), 100.0), ("b".to_string(), "keep".to_string(), 60.0)];
+        assert!(!plan(&a, Weights::default(), &map, &rules, &diplomacy, &armies).iter().any(|o| o.starts_with("declare_war")));
+    }
+
+    #[test]
+    fn recruits_within_budget() {
+        let (map, rules) = (map(), rules());
+        let settlement = || Settlement::new("Hold".to_string(), Vec::new(), 1, Vec::new(), 1000, String::new());
+        let weights = Weights { economy: 0.0, ..Weights::default() };
+        let diplomacy = Diplomacy::new(vec!["a".to_string()]);
+
+        /* Three quarters of 500 gold buy spearmen but no knights */
+        let a = faction("a", Vec::new(), vec![settlement()], 500);
+        let orders = plan(&a, weights.clone(), &map, &rules, &diplomacy, &[]);
+        assert!(orders.contains(&"recruit spearmen Hold".to_string()));
+        assert!(!orders.iter().any(|o| o.starts_with("recruit knights")));
+
+        /* The reserve is never spent */
+        let a = faction("a", Vec::new(), vec![settlement()], 100);
+        assert!(!plan(&a, weights, &map, &rules, &diplomacy, &[]).iter().any(|o| o.starts_with("recruit")));
+    }
+
+    #[test]
+    fn leaves_a_stronger_stack_alone() {
+        let (map, rules) = (map(), rules());
+        let first = army("First", "home", 3, &rules);
+        let power = first.power(&rules);
+        let a = faction("a", vec![first], Vec::new(), 0);
+        let mut diplomacy = Diplomacy::new(vec!["a".to_string(), "b".to_string()]);
+        diplomacy.set_state("a", "b", State::War);
+
+        let armies = [("a".to_string(), "home".to_string(), power), ("b".to_string(), "keep".to_string(), power * 2.0)];
+        let orders = plan(&a, Weights::default(), &map, &rules, &diplomacy, &armies);
+        assert!(orders.contains(&"move border First".to_string()));
+        assert!(!orders.contains(&"move keep First".to_string()));
+
+        /* With every region held too strongly the army stays put */
+        let armies = [armies[0].clone(), armies[1].clone(), ("b".to_string(), "border".to_string(), power * 2.0)];
+        assert!(!plan(&a, Weights::default(), &map, &rules, &diplomacy, &armies).iter().any(|o| o.starts_with("move")));
+    }
+}
@@ -0,0 +1,489 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::diplomacy::Diplomacy;
+use super::faction::{army::Army, character::character_trait::Deed, fleet::Fleet};
+use super::map::{landscape::Landscape, region::Region, Map};
+use super::rules::Rules;
+
+/* Upper limit of combat rounds, the battle ends earlier once only one side is left standing */
+const ROUNDS: u32 = 6;
+/* Share of a side's power that is dealt as casualties every round */
+const LETHALITY: f32 = 0.05;
+/* Sides below this average morale flee the field */
+const BREAKING_MORALE: u32 = 25;
+const WINNER_MORALE: i32 = 10;
+const LOSER_MORALE: i32 = -15;
+/* Experience for every unit that lives through a battle */
+const WINNER_EXPERIENCE: u32 = 2;
+const LOSER_EXPERIENCE: u32 = 1;
+/* Share of a side's ships every round of a naval battle sinks on the other side */
+const NAVAL_LETHALITY: f32 = 0.1;
+
+/* All armies of one faction that stand in the contested region */
+pub struct BattleSide {
+    pub faction: String,
+    pub armies: Vec<Army>,
+    /* The region owner fights with the landscape's defence bonus */
+    pub defender: bool,
+    /* Extra power of defenders behind walls, 0.5 means half again as strong */
+    pub fortification: f32,
+}
+
+impl BattleSide {
+    pub fn new(faction: String, armies: Vec<Army>, defender: bool, fortification: f32) -> Self {
+        BattleSide {
+            faction,
+            armies,
+            defender,
+            fortification,
+        }
+    }
+
+    fn get_men(&self) -> u32 {
+        self.armies.iter().map(|a| a.get_men()).sum()
+    }
+
+    fn get_morale(&self) -> u32 {
+        let men = self.get_men();
+        if men == 0 {
+            return 0;
+        }
+        let total: u32 = self.armies.iter().map(|a| a.get_men() * a.get_morale()).sum();
+        total / men
+    }
+
+    fn power(&self, landscape: &Landscape, rules: &Rules) -> f32 {
+        let power: f32 = self.armies.iter().map(|a| a.power(rules)).sum();
+        if self.defender {
+            power * (1.0 + landscape.get_defence_bonus() + self.fortification)
+        } else {
+            power
+        }
+    }
+
+    fn take_damage(&mut self, damage: f32, rules: &Rules) -> u32 {
+        let total = self.get_men();
+        let mut lost = 0;
+        for a in self.armies.iter_mut() {
+            if total == 0 {
+                break;
+            }
+            let share = damage * a.get_men() as f32 / total as f32;
+            lost += a.take_damage(share, rules);
+        }
+        lost
+    }
+
+    fn change_morale(&mut self, change: i32) {
+        for a in self.armies.iter_mut() {
+            a.change_morale(change);
+        }
+    }
+
+    fn is_broken(&self) -> bool {
+        self.get_men() == 0 || self.get_morale() < BREAKING_MORALE
+    }
+}
+
+/* All vectors are indexed like the sides that were passed to resolve */
+pub struct BattleResult {
+    pub winner: usize,
+    pub casualties: Vec<u32>,
+    pub morale_changes: Vec<i32>,
+    /* Sides that lost and have to leave the region */
+    pub retreating: Vec<usize>,
+    /* Names of the armies of every side that were wiped out, they are removed from their side */
+    pub destroyed: Vec<Vec<String>>,
+}
+
+/* Splits the factions meeting in a region into the two coalitions of a battle. The first faction,
+usually the region owner, leads coalition 0 with its allies and vassals, everyone at war with it
+forms coalition 1. Factions that are neither stay out of the battle and get None. */
+pub fn coalitions(factions: &[&str], diplomacy: &Diplomacy) -> Vec<Option<usize>> {
+    let first = match factions.first() {
+        Some(f) => *f,
+        None => return Vec::new(),
+    };
+    factions
+        .iter()
+        .map(|f| {
+            if diplomacy.has_military_access(first, f) {
+                Some(0)
+            } else if diplomacy.at_war(first, f) {
+                Some(1)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/* Fights the battle out round by round. Sides only hurt the sides of the other coalition, the
+winner's whole coalition holds the field. The outcome only depends on the sides, the landscape
+and the seed, so the same battle always ends the same way. */
+pub fn resolve(sides: &mut [BattleSide], coalition: &[usize], landscape: &Landscape, rules: &Rules, seed: u64) -> BattleResult {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let morale_before: Vec<u32> = sides.iter().map(|s| s.get_morale()).collect();
+    let fielded: Vec<Vec<String>> = sides.iter().map(|s| s.armies.iter().filter(|a| a.get_men() > 0).map(|a| a.get_name().to_string()).collect()).collect();
+    let mut casualties: Vec<u32> = vec![0; sides.len()];
+
+    for _ in 0..ROUNDS {
+        let fighting: Vec<usize> = (0..sides.len()).filter(|&i| !sides[i].is_broken()).collect();
+        if fighting.iter().all(|&i| coalition[i] == coalition[fighting[0]]) {
+            break;
+        }
+
+        let powers: Vec<f32> = sides.iter().map(|s| s.power(landscape, rules)).collect();
+        let mut incoming: Vec<f32> = vec![0.0; sides.len()];
+
+        /* Every side splits its damage over its enemies by their power */
+        for &i in &fighting {
+            let enemies: Vec<usize> = fighting.iter().copied().filter(|&j| coalition[j] != coalition[i]).collect();
+            if enemies.is_empty() {
+                continue;
+            }
+            let damage = powers[i] * LETHALITY * rng.gen_range(0.75..1.25);
+            let enemy_power: f32 = enemies.iter().map(|&j| powers[j]).sum();
+            for &j in &enemies {
+                if enemy_power > 0.0 {
+                    incoming[j] += damage * powers[j] / enemy_power;
+                } else {
+                    incoming[j] += damage / enemies.len() as f32;
+                }
+            }
+        }
+
+        for &i in &fighting {
+            let men = sides[i].get_men();
+            let lost = sides[i].take_damage(incoming[i], rules);
+            casualties[i] += lost;
+
+            /* Losing a tenth of the men costs ten points of morale */
+            if let Some(share) = (lost * 100).checked_div(men) {
+                sides[i].change_morale(-(share as i32));
+            }
+        }
+    }
+
+    let powers: Vec<f32> = sides.iter().map(|s| s.power(landscape, rules)).collect();
+    let mut winner = 0;
+    for i in 1..sides.len() {
+        let better = match (sides[i].is_broken(), sides[winner].is_broken()) {
+            (false, true) => true,
+            (true, false) => false,
+            _ => powers[i] > powers[winner],
+        };
+        if better {
+            winner = i;
+        }
+    }
+
+    let mut retreating: Vec<usize> = Vec::new();
+    for (i, s) in sides.iter_mut().enumerate() {
+        if coalition[i] == coalition[winner] {
+            s.change_morale(WINNER_MORALE);
+            s.armies.iter_mut().for_each(|a| a.gain_experience(WINNER_EXPERIENCE));
+        } else {
+            s.change_morale(LOSER_MORALE);
+            s.armies.iter_mut().for_each(|a| a.gain_experience(LOSER_EXPERIENCE));
+            retreating.push(i);
+        }
+    }
+
+    /* Armies that had men before the battle and lost all of them are gone. Army names are only
+    unique within a faction, so every side only looks at its own armies. */
+    let mut destroyed: Vec<Vec<String>> = Vec::new();
+    for (i, s) in sides.iter_mut().enumerate() {
+        let wiped_out = |a: &Army| a.get_men() == 0 && fielded[i].iter().any(|n| n == a.get_name());
+        destroyed.push(s.armies.iter().filter(|a| wiped_out(a)).map(|a| a.get_name().to_string()).collect());
+        s.armies.retain(|a| !wiped_out(a));
+    }
+
+    let morale_changes = sides.iter().enumerate().map(|(i, s)| s.get_morale() as i32 - morale_before[i] as i32).collect();
+
+    BattleResult {
+        winner,
+        casualties,
+        morale_changes,
+        retreating,
+        destroyed,
+    }
+}
+
+/* Generals of the sides that hold the field may earn a trait for the victory, the retreating ones
+one for the defeat. Returns the faction, general and trait names. */
+pub fn reward_generals(sides: &mut [BattleSide], retreating: &[usize], rules: &Rules, seed: u64) -> Vec<(String, String, String)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut rewarded: Vec<(String, String, String)> = Vec::new();
+    for (i, s) in sides.iter_mut().enumerate() {
+        let deed = if retreating.contains(&i) { Deed::BattleLost } else { Deed::BattleWon };
+        for g in s.armies.iter_mut().filter_map(|a| a.get_general_mut().as_mut()) {
+            if let Some(t) = g.gain_trait(deed, rules, &mut rng) {
+                rewarded.push((s.faction.clone(), g.get_name().to_string(), t));
+            }
+        }
+    }
+    rewarded
+}
+
+/* All fleets of one faction in the contested region */
+pub struct NavalSide {
+    pub faction: String,
+    pub fleets: Vec<Fleet>,
+}
+
+impl NavalSide {
+    pub fn new(faction: String, fleets: Vec<Fleet>) -> Self {
+        NavalSide { faction, fleets }
+    }
+
+    fn get_ships(&self) -> u32 {
+        self.fleets.iter().map(|f| f.get_ships()).sum()
+    }
+
+    /* Losses are split over the fleets by their size. Returns the men that drowned. */
+    fn sink(&mut self, ships: u32) -> u32 {
+        let total = self.get_ships();
+        let mut left = ships.min(total);
+        let mut drowned = 0;
+        for f in self.fleets.iter_mut() {
+            let share = ((ships as f32 * f.get_ships() as f32 / total as f32).ceil() as u32).min(left);
+            drowned += f.sink(share);
+            left -= share;
+        }
+        drowned
+    }
+}
+
+/* All vectors are indexed like the sides that were passed to resolve_naval */
+pub struct NavalResult {
+    pub winner: usize,
+    pub sunk: Vec<u32>,
+    pub drowned: Vec<u32>,
+    /* Sides that lost and have to leave the region */
+    pub retreating: Vec<usize>,
+}
+
+/* Ships fight ships, only their number counts. Coalitions are those of resolve. Fleets without
+ships left are removed from their side together with the armies they carried. */
+pub fn resolve_naval(sides: &mut [NavalSide], coalition: &[usize], seed: u64) -> NavalResult {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sunk: Vec<u32> = vec![0; sides.len()];
+    let mut drowned: Vec<u32> = vec![0; sides.len()];
+
+    for _ in 0..ROUNDS {
+        let fighting: Vec<usize> = (0..sides.len()).filter(|&i| sides[i].get_ships() > 0).collect();
+        if fighting.iter().all(|&i| coalition[i] == coalition[fighting[0]]) {
+            break;
+        }
+
+        let ships: Vec<f32> = sides.iter().map(|s| s.get_ships() as f32).collect();
+        let mut incoming: Vec<f32> = vec![0.0; sides.len()];
+        for &i in &fighting {
+            let enemies: Vec<usize> = fighting.iter().copied().filter(|&j| coalition[j] != coalition[i]).collect();
+            let damage = ships[i] * NAVAL_LETHALITY * rng.gen_range(0.75..1.25);
+            let enemy_ships: f32 = enemies.iter().map(|&j| ships[j]).sum();
+            for &j in &enemies {
+                incoming[j] += damage * ships[j] / enemy_ships;
+            }
+        }
+
+        /* Fractions of a ship sink it by chance, so small fleets can hurt big ones too */
+        for &i in &fighting {
+            let lost = ((incoming[i] + rng.gen_range(0.0..1.0)).floor() as u32).min(sides[i].get_ships());
+            sunk[i] += lost;
+            drowned[i] += sides[i].sink(lost);
+        }
+    }
+
+    let mut winner = 0;
+    for i in 1..sides.len() {
+        if sides[i].get_ships() > sides[winner].get_ships() {
+            winner = i;
+        }
+    }
+
+    for s in sides.iter_mut() {
+        s.fleets.retain(|f| f.get_ships() > 0);
+    }
+
+    NavalResult {
+        winner,
+        sunk,
+        drowned,
+        retreating: (0..sides.len()).filter(|&i| coalition[i] != coalition[winner]).collect(),
+    }
+}
+
+/* Moves a beaten fleet to a neighbouring sea region, a fleet with nowhere to go is scuttled */
+pub fn retreat_fleets(side: &mut NavalSide, region: &Region, map: &Map, diplomacy: &Diplomacy) {
+    let retreat = region.get_sea_neighbours().iter().find(|n| {
+        let r = map.get_region(n).unwrap();
+        map.is_naval(r) && diplomacy.may_enter(&side.faction, r)
+    });
+    match retreat {
+        Some(r) => {
+            for f in side.fleets.iter_mut() {
+                f.set_region(r.clone());
+            }
+        }
+        None => side.fleets.clear(),
+    }
+}
+
+/* Moves a beaten side to a neighbouring region it may enter and that is not held by an enemy.
+Without such a region the armies surrender and are lost. */
+pub fn retreat(side: &mut BattleSide, region: &Region, map: &Map, diplomacy: &Diplomacy) {
+    let retreat = region.get_land_neighbours().iter().find(|n| {
+        let r = map.get_region(n).unwrap();
+        let hostile = r.get_owner().is_some_and(|o| diplomacy.at_war(&side.faction, o));
+        map.entry_cost(r).is_some() && diplomacy.may_enter(&side.faction, r) && !hostile
+    });
+    match retreat {
+        Some(r) => {
+            let r = map.get_region(r).unwrap();
+            for a in side.armies.iter_mut() {
+                a.set_region(r);
+            }
+        }
+        None => side.armies.clear(),
+    }
+}
+
+/* Mixes the campaign seed with the turn and the region, so every battle gets its own stream */
+pub fn battle_seed(seed: u64, turn: u32, region: &str) -> u64 {
+    /* FNV-1a, stable across builds unlike the std hasher */
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in region.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    seed ^ hash ^ ((turn as u64) << 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::game::diplomacy::State;
+    use crate::game::faction::army::unit::{unit_type::UnitType, Unit};
+    use crate::game::queue::Queue;
+
+    const SEED: u64 = 7;
+
+    fn rules() -> Rules {
+        let mut spearmen: UnitType = serde_json::from_value(json!({
+            "name": "Spearmen", "men": 100, "attack": 3, "defence": 5, "health": 2, "morale": 60, "speed": 6,
+            "cost": {"gold": 80, "food": 0, "manpower": 25}, "upkeep": {"gold": 8, "food": 5, "manpower": 0}, "turns": 1
+        }))
+        .unwrap();
+        spearmen.set_id("spearmen".to_string());
+        Rules::new(vec![spearmen], Vec::new(), Vec::new(), Vec::new(), Vec::new())
+    }
+
+    fn plains() -> Landscape {
+        serde_json::from_value(json!({"name": "plains", "movement_cost": 2, "passable": true, "defence_bonus": 0.0})).unwrap()
+    }
+
+    fn army(name: &str, units: usize, rules: &Rules) -> Army {
+        let units = (0..units).map(|_| Unit::new(rules.get_unit_type("spearmen").unwrap())).collect();
+        Army::new(name.to_string(), (0.0, 0.0), "field".to_string(), units, Queue::new())
+    }
+
+    fn side(faction: &str, armies: Vec<Army>) -> BattleSide {
+        BattleSide::new(faction.to_string(), armies, false, 0.0)
+    }
+
+    #[test]
+    fn same_seed_same_outcome() {
+        let rules = rules();
+        let fight = |seed: u64| {
+            let mut sides = vec![side("a", vec![army("First", 5, &rules)]), side("b", vec![army("Second", 5, &rules)])];
+            let result = resolve(&mut sides, &[0, 1], &plains(), &rules, seed);
+            let men: Vec<u32> = sides.iter().map(|s| s.get_men()).collect();
+            (result.winner, result.casualties, result.morale_changes, men)
+        };
+
+        assert_eq!(fight(SEED), fight(SEED));
+        assert_ne!(fight(SEED).1, fight(SEED + 1).1);
+    }
+
+    #[test]
+    fn stronger_side_wins() {
+        let rules = rules();
+        let mut sides = vec![side("a", vec![army("Small", 2, &rules)]), side("b", vec![army("Large", 10, &rules)])];
+        let result = resolve(&mut sides, &[0, 1], &plains(), &rules, SEED);
+
+        assert_eq!(result.winner, 1);
+        assert_eq!(result.retreating, vec![0]);
+        assert!(result.casualties[0] > 0 && result.casualties[1] > 0);
+        assert!(result.casualties[0] > result.casualties[1]);
+        assert!(result.morale_changes[0] < result.morale_changes[1]);
+    }
+
+    #[test]
+    fn allies_do_not_hurt_each_other() {
+        let rules = rules();
+        let mut sides = vec![side("a", vec![army("First", 3, &rules)]), side("b", vec![army("Second", 3, &rules)])];
+        let result = resolve(&mut sides, &[0, 0], &plains(), &rules, SEED);
+        assert_eq!(result.casualties, vec![0, 0]);
+        assert!(result.retreating.is_empty());
+
+        let mut sides = vec![side("a", vec![army("First", 3, &rules)]), side("c", vec![army("Third", 3, &rules)]), side("b", vec![army("Second", 3, &rules)])];
+        let result = resolve(&mut sides, &[0, 1, 0], &plains(), &rules, SEED);
+        assert_eq!(result.retreating, vec![1]);
+        assert!(result.casualties.iter().all(|&c| c > 0));
+    }
+
+    #[test]
+    fn neutrals_stay_out_of_the_battle() {
+        let mut diplomacy = Diplomacy::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        diplomacy.set_state("a", "b", State::War);
+        assert_eq!(coalitions(&["a", "b", "c"], &diplomacy), vec![Some(0), Some(1), None]);
+
+        /* Allies and vassals of the first faction join its side */
+        diplomacy.set_state("c", "a", State::Alliance);
+        assert_eq!(coalitions(&["a", "b", "c"], &diplomacy), vec![Some(0), Some(1), Some(0)]);
+
+        diplomacy.set_state("c", "a", State::Vassal);
+        assert_eq!(coalitions(&["a", "c", "b"], &diplomacy), vec![Some(0), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn destroyed_armies_stay_with_their_side() {
+        let rules = rules();
+        let mut beaten = army("Levy", 1, &rules);
+        beaten.take_damage(190.0, &rules);
+        let mut sides = vec![side("a", vec![army("Levy", 10, &rules)]), side("b", vec![beaten])];
+        let result = resolve(&mut sides, &[0, 1], &plains(), &rules, SEED);
+
+        assert_eq!(result.destroyed, vec![Vec::<String>::new(), vec!["Levy".to_string()]]);
+        assert_eq!(sides[0].armies.len(), 1);
+        assert!(sides[1].armies.is_empty());
+    }
+
+    #[test]
+    fn beaten_side_retreats_away_from_enemies() {
+        let rules = rules();
+        let region = |id: &str, owner: Option<&str>, neighbours: &[&str]| {
+            json!({"id": id, "name": id, "owner": owner, "landscape": "plains", "settlement": null, "position": [0.0, 0.0], "land_neighbours": neighbours, "sea_neighbours": []})
+        };
+        let map: Map = serde_json::from_value(json!({
+            "landscapes": [{"name": "plains", "movement_cost": 2, "passable": true, "defence_bonus": 0.0}],
+            "regions": [region("field", None, &["enemy_land", "hills"]), region("enemy_land", Some("c"), &["field"]), region("hills", None, &["field"]), region("pocket", None, &["enemy_land"])]
+        }))
+        .unwrap();
+        let mut diplomacy = Diplomacy::new(vec!["a".to_string(), "c".to_string()]);
+        diplomacy.set_state("a", "c", State::War);
+
+        let mut beaten = side("a", vec![army("First", 2, &rules)]);
+        retreat(&mut beaten, map.get_region("field").unwrap(), &map, &diplomacy);
+        assert_eq!(beaten.armies[0].get_region(), "hills");
+
+        /* Surrounded by enemies the armies surrender */
+        let mut trapped = side("a", vec![army("First", 2, &rules)]);
+        retreat(&mut trapped, map.get_region("pocket").unwrap(), &map, &diplomacy);
+        assert!(trapped.armies.is_empty());
+    }
+}
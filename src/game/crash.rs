@@ -0,0 +1,44 @@
+use std::{backtrace::Backtrace, fs, panic, sync::Mutex, time::{SystemTime, UNIX_EPOCH}};
+
+use super::Game;
+
+/* What the last panic was about and where it happened, filled by the panic hook */
+static PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/* The hook runs while the game may still be borrowed by the main loop, so it only keeps the panic
+message and backtrace. The main loop writes them out with write_report once the panic has unwound. */
+pub fn install_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = format!("{}\n\n{}", info, Backtrace::force_capture());
+        if let Ok(mut p) = PANIC.lock() {
+            *p = Some(report);
+        }
+    }));
+}
+
+/* Tries to save the running campaign into the slot, then writes a crash report with the panic and
+the outcome of the save into the crash report directory */
+pub fn write_report(game: &Game, slot: &str) {
+    let saved = match (&game.player_faction, game.save_game(slot)) {
+        (None, _) => "No campaign was running".to_string(),
+        (Some(p), Ok(_)) => format!("Turn {} of {} was saved in the slot {}", game.turn_manager.get_turn(), p.get_id(), slot),
+        (Some(_), Err(e)) => format!("The campaign could not be saved: {}", e),
+    };
+    let panic = match PANIC.lock() {
+        Ok(mut p) => p.take().unwrap_or_else(|| "Unknown panic".to_string()),
+        Err(_) => "Unknown panic".to_string(),
+    };
+
+    /* The command log lets the crash be replayed */
+    game.write_command_log();
+
+    let directory = game.paths.resource_manager.get_crash_reports();
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let file = format!("{}crash_{}.txt", directory, timestamp);
+    match fs::create_dir_all(&directory).and_then(|_| fs::write(&file, format!("{}\n\n{}\n", saved, panic))) {
+        Ok(_) => println!("{}, the crash report was written to {}", saved, file),
+        Err(e) => println!("{}, the crash report could not be written to {}: {}", saved, file, e),
+    }
+}
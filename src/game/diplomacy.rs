@@ -0,0 +1,399 @@
+use serde::{Deserialize, Serialize};
+
+use super::faction::REBELS;
+use super::map::region::Region;
+
+/* How long event driven opinion changes last */
+const MODIFIER_TURNS: u32 = 10;
+/* Turns a treaty proposal waits for an answer */
+const PROPOSAL_TURNS: u32 = 2;
+/* Opinion of the proposer a faction needs before it agrees to trade */
+const TRADE_OPINION: i32 = -10;
+const MIN_OPINION: i32 = -100;
+const MAX_OPINION: i32 = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    War,
+    Peace,
+    NonAggression,
+    Alliance,
+    /* The first faction of the relation serves the second one */
+    Vassal,
+}
+
+impl State {
+    pub fn from_name(name: &str) -> Option<State> {
+        match name {
+            "war" => Some(State::War),
+            "peace" => Some(State::Peace),
+            "non_aggression" => Some(State::NonAggression),
+            "alliance" => Some(State::Alliance),
+            "vassal" => Some(State::Vassal),
+            _ => None,
+        }
+    }
+
+    /* Opinion every pair of factions in this state starts from */
+    fn base_opinion(self) -> i32 {
+        match self {
+            State::War => -30,
+            State::Peace => 0,
+            State::NonAggression => 10,
+            State::Alliance => 40,
+            State::Vassal => 20,
+        }
+    }
+
+    /* Opinion of the proposer a faction needs before it agrees to the treaty */
+    fn required_opinion(self) -> i32 {
+        match self {
+            State::War => MAX_OPINION + 1,
+            State::Peace => -30,
+            State::NonAggression => 0,
+            State::Alliance => 40,
+            State::Vassal => 80,
+        }
+    }
+
+    /* Leaving these states by declaring war angers everyone */
+    fn is_treaty(self) -> bool {
+        matches!(self, State::NonAggression | State::Alliance | State::Vassal)
+    }
+}
+
+/* What a proposal asks for, a new state or a trade agreement next to the state */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Offer {
+    Treaty(State),
+    Trade,
+}
+
+impl Offer {
+    fn required_opinion(self) -> i32 {
+        match self {
+            Offer::Treaty(state) => state.required_opinion(),
+            Offer::Trade => TRADE_OPINION,
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Offer::Treaty(state) => format!("{:?}", state),
+            Offer::Trade => "a trade agreement".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Relation {
+    factions: (String, String),
+    state: State,
+}
+
+impl Relation {
+    fn is_between(&self, a: &str, b: &str) -> bool {
+        (self.factions.0 == a && self.factions.1 == b) || (self.factions.0 == b && self.factions.1 == a)
+    }
+}
+
+/* A border incident, a broken treaty and so on. The value fades away after a while. */
+#[derive(Serialize, Deserialize, Clone)]
+struct OpinionModifier {
+    reason: String,
+    value: i32,
+    turns_left: u32,
+}
+
+/* What the first faction thinks of the second one */
+#[derive(Serialize, Deserialize, Clone)]
+struct Opinion {
+    factions: (String, String),
+    modifiers: Vec<OpinionModifier>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Proposal {
+    from: String,
+    to: String,
+    /* For vassal treaties the receiving faction is asked to serve the proposing one */
+    offer: Offer,
+    turns_left: u32,
+}
+
+/* Relations between every pair of factions. Pairs without an entry are at peace. */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Diplomacy {
+    factions: Vec<String>,
+    relations: Vec<Relation>,
+    opinions: Vec<Opinion>,
+    proposals: Vec<Proposal>,
+    /* Pairs of factions whose settlements trade with each other, they end with a war */
+    #[serde(default)]
+    trade_agreements: Vec<(String, String)>,
+}
+
+impl Diplomacy {
+    pub fn new(factions: Vec<String>) -> Self {
+        Diplomacy {
+            factions,
+            relations: Vec::new(),
+            opinions: Vec::new(),
+            proposals: Vec::new(),
+            trade_agreements: Vec::new(),
+        }
+    }
+
+    pub fn get_state(&self, a: &str, b: &str) -> State {
+        match self.relations.iter().find(|r| r.is_between(a, b)) {
+            Some(r) => r.state,
+            None => State::Peace,
+        }
+    }
+
+    /* Vassal relations are set with the vassal first */
+    pub fn set_state(&mut self, a: &str, b: &str, state: State) {
+        self.relations.retain(|r| !r.is_between(a, b));
+        if state == State::War {
+            self.end_trade(a, b);
+        }
+        if state != State::Peace {
+            self.relations.push(Relation { factions: (a.to_string(), b.to_string()), state });
+        }
+    }
+
+    /* Rebels are at war with everyone, no treaty is ever made with them */
+    pub fn at_war(&self, a: &str, b: &str) -> bool {
+        a != b && (a == REBELS || b == REBELS || self.get_state(a, b) == State::War)
+    }
+
+    /* Allies, vassals and their overlords share their lands with each other */
+    pub fn has_military_access(&self, a: &str, b: &str) -> bool {
+        a == b || matches!(self.get_state(a, b), State::Alliance | State::Vassal)
+    }
+
+    /* Armies may enter unclaimed land, their own and friendly regions, and the lands of enemies */
+    pub fn may_enter(&self, faction: &str, region: &Region) -> bool {
+        match region.get_owner() {
+            Some(o) => self.has_military_access(faction, o) || self.at_war(faction, o),
+            None => true,
+        }
+    }
+
+    pub fn get_opinion(&self, from: &str, to: &str) -> i32 {
+        let modifiers: i32 = self
+            .opinions
+            .iter()
+            .filter(|o| o.factions.0 == from && o.factions.1 == to)
+            .flat_map(|o| o.modifiers.iter())
+            .map(|m| m.value)
+            .sum();
+        (self.get_state(from, to).base_opinion() + modifiers).clamp(MIN_OPINION, MAX_OPINION)
+    }
+
+    pub fn add_modifier(&mut self, from: &str, to: &str, reason: &str, value: i32) {
+        let modifier = OpinionModifier { reason: reason.to_string(), value, turns_left: MODIFIER_TURNS };
+        match self.opinions.iter_mut().find(|o| o.factions.0 == from && o.factions.1 == to) {
+            Some(o) => o.modifiers.push(modifier),
+            None => self.opinions.push(Opinion { factions: (from.to_string(), to.to_string()), modifiers: vec![modifier] }),
+        }
+    }
+
+    /* Armies of two factions that are not at war met in the same region */
+    pub fn border_incident(&mut self, a: &str, b: &str) {
+        if self.has_military_access(a, b) {
+            return;
+        }
+        self.add_modifier(a, b, "Border incident", -5);
+        self.add_modifier(b, a, "Border incident", -5);
+    }
+
+    pub fn battle_fought(&mut self, defender: &str, attacker: &str) {
+        self.add_modifier(defender, attacker, "Attacked our lands", -10);
+    }
+
+    pub fn declare_war(&mut self, from: &str, to: &str) {
+        if !self.factions.iter().any(|f| f == to) {
+            println!("{} can not declare war on {}", from, to);
+            return;
+        }
+        if from == to || self.at_war(from, to) {
+            return;
+        }
+
+        if self.get_state(from, to).is_treaty() {
+            let others: Vec<String> = self.factions.iter().filter(|f| *f != from).cloned().collect();
+            for f in others {
+                self.add_modifier(&f, from, "Broke a treaty", -20);
+            }
+        }
+        self.add_modifier(to, from, "Declared war on us", -30);
+        self.set_state(from, to, State::War);
+        println!("{} has declared war on {}", from, to);
+    }
+
+    pub fn propose(&mut self, from: &str, to: &str, treaty: State) {
+        if from == to || from == REBELS || treaty == State::War || self.get_state(from, to) == treaty || !self.factions.iter().any(|f| f == to) {
+            println!("{} can not propose {:?} to {}", from, treaty, to);
+            return;
+        }
+        self.proposals.push(Proposal { from: from.to_string(), to: to.to_string(), offer: Offer::Treaty(treaty), turns_left: PROPOSAL_TURNS });
+    }
+
+    /* Only factions at peace trade */
+    pub fn propose_trade(&mut self, from: &str, to: &str) {
+        if from == to || self.at_war(from, to) || self.has_trade_agreement(from, to) || !self.factions.iter().any(|f| f == to) {
+            println!("{} can not propose a trade agreement to {}", from, to);
+            return;
+        }
+        self.proposals.push(Proposal { from: from.to_string(), to: to.to_string(), offer: Offer::Trade, turns_left: PROPOSAL_TURNS });
+    }
+
+    pub fn has_trade_agreement(&self, a: &str, b: &str) -> bool {
+        self.trade_agreements.iter().any(|(x, y)| (x == a && y == b) || (x == b && y == a))
+    }
+
+    /* Factions the faction has a trade agreement with */
+    pub fn get_trade_partners(&self, faction: &str) -> Vec<&str> {
+        self.trade_agreements
+            .iter()
+            .filter_map(|(a, b)| if a == faction { Some(b.as_str()) } else if b == faction { Some(a.as_str()) } else { None })
+            .collect()
+    }
+
+    pub fn cancel_trade(&mut self, from: &str, to: &str) {
+        if !self.has_trade_agreement(from, to) {
+            println!("{} has no trade agreement with {}", from, to);
+            return;
+        }
+        self.end_trade(from, to);
+        self.add_modifier(to, from, "Cancelled our trade", -10);
+        println!("{} has cancelled the trade agreement with {}", from, to);
+    }
+
+    fn end_trade(&mut self, a: &str, b: &str) {
+        self.trade_agreements.retain(|(x, y)| !((x == a && y == b) || (x == b && y == a)));
+    }
+
+    /* index counts only the proposals made to the answering faction */
+    pub fn answer(&mut self, faction: &str, index: usize, accept: bool) {
+        match self.proposals.iter().enumerate().filter(|(_, p)| p.to == faction).nth(index) {
+            Some((i, _)) => {
+                let proposal = self.proposals.remove(i);
+                self.conclude(proposal, accept);
+            }
+            None => println!("{} has no proposal {}", faction, index),
+        }
+    }
+
+    fn conclude(&mut self, proposal: Proposal, accept: bool) {
+        if !accept {
+            self.add_modifier(&proposal.from, &proposal.to, "Declined our proposal", -5);
+            println!("{} has declined {} with {}", proposal.to, proposal.offer.describe(), proposal.from);
+            return;
+        }
+
+        match proposal.offer {
+            /* A war may have broken out or both may have offered trade while the proposal waited */
+            Offer::Trade if self.at_war(&proposal.from, &proposal.to) || self.has_trade_agreement(&proposal.from, &proposal.to) => return,
+            Offer::Trade => self.trade_agreements.push((proposal.from.clone(), proposal.to.clone())),
+            Offer::Treaty(State::Vassal) => self.set_state(&proposal.to, &proposal.from, State::Vassal),
+            Offer::Treaty(treaty) => self.set_state(&proposal.from, &proposal.to, treaty),
+        }
+        self.add_modifier(&proposal.from, &proposal.to, "Signed a treaty", 10);
+        self.add_modifier(&proposal.to, &proposal.from, "Signed a treaty", 10);
+        println!("{} has accepted {} with {}", proposal.to, proposal.offer.describe(), proposal.from);
+    }
+
+    /* Factions without a player accept treaties their opinion of the proposer allows for */
+    pub fn answer_ai_proposals(&mut self, player: &str) {
+        let answers: Vec<(usize, bool)> = self
+            .proposals
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.to != player)
+            .map(|(i, p)| (i, self.get_opinion(&p.to, &p.from) >= p.offer.required_opinion()))
+            .collect();
+
+        for (i, accept) in answers.into_iter().rev() {
+            let proposal = self.proposals.remove(i);
+            self.conclude(proposal, accept);
+        }
+    }
+
+    /* Lets opinion modifiers fade and unanswered proposals run out */
+    pub fn end_turn(&mut self) {
+        for o in self.opinions.iter_mut() {
+            for m in o.modifiers.iter_mut() {
+                m.turns_left = m.turns_left.saturating_sub(1);
+            }
+            o.modifiers.retain(|m| m.turns_left > 0);
+        }
+        self.opinions.retain(|o| !o.modifiers.is_empty());
+
+        for p in self.proposals.iter_mut() {
+            p.turns_left = p.turns_left.saturating_sub(1);
+        }
+        self.proposals.retain(|p| p.turns_left > 0);
+    }
+
+    /* Diplomatic event codes look like "declare_war <faction id>", "propose <treaty> <faction id>",
+    "propose trade <faction id>", "cancel_trade <faction id>", "accept_treaty <index>" and "decline_treaty <index>" */
+    pub fn retreive_event_code(&mut self, code: &str, player: &str) {
+        let mut split_string = code.split(' ');
+        let command = split_string.next().unwrap_or("");
+        let arguments: Vec<&str> = split_string.collect();
+
+        match (command, &arguments[..]) {
+            ("declare_war", [faction]) => self.declare_war(player, faction),
+            ("propose", ["trade", faction]) => self.propose_trade(player, faction),
+            ("cancel_trade", [faction]) => self.cancel_trade(player, faction),
+            ("propose", [treaty, faction]) => match State::from_name(treaty) {
+                Some(t) => self.propose(player, faction, t),
+                None => println!("There is no treaty called {}", treaty),
+            },
+            /* Answers refer to proposals that may be gone already, a bad one is only reported */
+            ("accept_treaty", [index]) | ("decline_treaty", [index]) => match index.parse() {
+                Ok(i) => self.answer(player, i, command == "accept_treaty"),
+                Err(_) => println!("{} is not a valid proposal index", index),
+            },
+            ("accept_treaty", _) | ("decline_treaty", _) => println!("{} needs the index of a proposal", command),
+            ("declare_war", _) | ("propose", _) | ("cancel_trade", _) => panic!("Order is missing parameters: {}", code),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diplomacy() -> Diplomacy {
+        Diplomacy::new(vec!["gondor".to_string(), "mordor".to_string()])
+    }
+
+    #[test]
+    fn bad_treaty_answers_are_ignored() {
+        let mut diplomacy = diplomacy();
+        diplomacy.propose("mordor", "gondor", State::NonAggression);
+        for code in ["accept_treaty x", "decline_treaty", "accept_treaty 3"] {
+            diplomacy.retreive_event_code(code, "gondor");
+        }
+        assert_eq!(diplomacy.proposals.len(), 1);
+
+        diplomacy.retreive_event_code("accept_treaty 0", "gondor");
+        assert!(diplomacy.proposals.is_empty());
+    }
+
+    #[test]
+    fn war_needs_a_known_faction() {
+        let mut diplomacy = diplomacy();
+        diplomacy.retreive_event_code("declare_war rohan", "gondor");
+        assert!(diplomacy.relations.is_empty());
+
+        diplomacy.retreive_event_code("declare_war mordor", "gondor");
+        assert!(diplomacy.at_war("gondor", "mordor"));
+    }
+}
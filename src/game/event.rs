@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::{Diplomacy, State};
+use super::faction::Faction;
+use super::map::Map;
+use super::resources::Resources;
+
+/* Stands for the player's faction wherever an event names a faction */
+pub const PLAYER: &str = "player";
+
+/* Something that happened during a turn, events are triggered by them */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Happening {
+    RegionOwnerChanged { region: String, owner: String },
+    FactionEliminated { faction: String },
+    BattleWon { faction: String, region: String },
+}
+
+/* What makes an event fire. Faction and region entries that are left out match anything. */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /* The turn has started */
+    Turn { turn: u32 },
+    RegionOwnerChanged { region: String, #[serde(default)] owner: Option<String> },
+    FactionEliminated { #[serde(default)] faction: Option<String> },
+    BattleWon { #[serde(default)] faction: Option<String>, #[serde(default)] region: Option<String> },
+}
+
+/* Has to hold for a triggered event to fire */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    PlayerIs { faction: String },
+    OwnsRegion { faction: String, region: String },
+    AtWar { faction: String, other: String },
+    TreasuryAtLeast { faction: String, resources: Resources },
+}
+
+/* What picking a choice does */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    /* Added to the treasury, negative values take from it */
+    Treasury { faction: String, resources: Resources },
+    Opinion { from: String, to: String, value: i32 },
+    Relation { faction: String, other: String, state: State },
+    /* New units of the unit type join the faction's army in the region */
+    AddUnits { faction: String, region: String, unit_type: String, count: u32 },
+    SetOwner { region: String, faction: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Choice {
+    /* Text file stem in assets/texts, shown on the choice's button */
+    pub text: String,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+/* Definition of a scripted event, loaded from data/events */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Event {
+    /* File stem of the event file */
+    #[serde(default)]
+    id: String,
+    trigger: Trigger,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    /* Text file stem in assets/texts */
+    text: String,
+    /* Events without choices only have a button to go on */
+    #[serde(default)]
+    choices: Vec<Choice>,
+    /* Fires every time it is triggered instead of once per campaign */
+    #[serde(default)]
+    repeatable: bool,
+}
+
+/* Everything the triggers and conditions of an event may look at */
+pub struct Context<'a> {
+    pub map: &'a Map,
+    pub diplomacy: &'a Diplomacy,
+    pub factions: Vec<&'a Faction>,
+    pub player: &'a str,
+    pub turn: u32,
+    pub happenings: &'a [Happening],
+}
+
+/* Turns "player" into the id of the player's faction */
+pub fn resolve<'a>(faction: &'a str, player: &'a str) -> &'a str {
+    if faction == PLAYER {
+        player
+    } else {
+        faction
+    }
+}
+
+impl Event {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    pub fn get_effects(&self) -> impl Iterator<Item = &Effect> {
+        self.choices.iter().flat_map(|c| c.effects.iter())
+    }
+
+    pub fn is_repeatable(&self) -> bool {
+        self.repeatable
+    }
+
+    pub fn is_triggered(&self, context: &Context) -> bool {
+        let matches = |wanted: &Option<String>, actual: &str| wanted.as_deref().is_none_or(|w| resolve(w, context.player) == actual);
+        match &self.trigger {
+            Trigger::Turn { turn } => *turn == context.turn,
+            Trigger::RegionOwnerChanged { region, owner } => context.happenings.iter().any(|h| match h {
+                Happening::RegionOwnerChanged { region: r, owner: o } => r == region && matches(owner, o),
+                _ => false,
+            }),
+            Trigger::FactionEliminated { faction } => context.happenings.iter().any(|h| match h {
+                Happening::FactionEliminated { faction: f } => matches(faction, f),
+                _ => false,
+            }),
+            Trigger::BattleWon { faction, region } => context.happenings.iter().any(|h| match h {
+                Happening::BattleWon { faction: f, region: r } => matches(faction, f) && matches(region, r),
+                _ => false,
+            }),
+        }
+    }
+
+    pub fn conditions_hold(&self, context: &Context) -> bool {
+        let faction = |id: &str| context.factions.iter().find(|f| f.get_id() == resolve(id, context.player)).copied();
+        self.conditions.iter().all(|c| match c {
+            Condition::PlayerIs { faction } => faction == context.player,
+            Condition::OwnsRegion { faction, region } => context.map.get_region(region).and_then(|r| r.get_owner()) == Some(resolve(faction, context.player)),
+            Condition::AtWar { faction, other } => context.diplomacy.at_war(resolve(faction, context.player), resolve(other, context.player)),
+            Condition::TreasuryAtLeast { faction: id, resources } => faction(id).is_some_and(|f| f.get_treasury().can_afford(*resources)),
+        })
+    }
+}
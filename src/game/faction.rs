@@ -1,25 +1,900 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-mod army;
-mod settlement;
+use super::ai::{self, Ai};
+use super::diplomacy::Diplomacy;
+use super::map::Map;
+use super::queue::Queue;
+use super::resources::Resources;
+use super::rules::Rules;
+use super::supply;
+use super::trade::TradeRoute;
+use super::turn::{Phase, Season};
+use super::victory::Condition;
+
+use army::unit::{unit_type::UnitType, Unit};
+use character::{character_trait::Deed, Character};
+
+pub(crate) mod army;
+pub(crate) mod character;
+pub(crate) mod fleet;
+pub(crate) mod settlement;
+
+/* Treasury of factions whose file does not set one */
+pub const STARTING_TREASURY: Resources = Resources { gold: 500, food: 200, manpower: 100 };
+/* Morale every army loses for each turn the treasury is in debt */
+const DEBT_MORALE: i32 = -10;
+/* After this many turns in debt units start to desert */
+const DEBT_TURNS_BEFORE_DISBANDING: u32 = 3;
+/* Regions an army sees around itself, high ground adds the landscape's sight bonus */
+const ARMY_SIGHT: u32 = 1;
+const FLEET_SIGHT: u32 = 1;
+/* Movement points an army spends to board a fleet and to get back on land */
+const EMBARK_COST: u32 = 2;
+const DISEMBARK_COST: u32 = 2;
+/* Id of the faction every rebel army belongs to. It is at war with everyone and never wins. */
+pub const REBELS: &str = "rebels";
+/* Rebels fight for their homes and do not back down easily */
+const REBEL_AGGRESSION: f32 = 1.5;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Faction {
+    /* File stem of the faction file, used by event codes like "set gondor" */
+    id: String,
     name: String,
+    /* Decides which unit types the faction may field, e.g. "elves" */
+    culture: String,
     armies: Option<Vec<army::Army>>,
     settlements: Option<Vec<settlement::Settlement>>,
+    #[serde(default)]
+    fleets: Vec<fleet::Fleet>,
+    treasury: Resources,
+    /* Turns in a row the treasury ended the income phase below zero */
+    debt_turns: u32,
+    /* All of them have to be met to win the campaign */
+    #[serde(default)]
+    victory_conditions: Vec<Condition>,
+    /* Region ids the faction can currently see, everything else is under the fog of war */
+    #[serde(default)]
+    visible_regions: Vec<String>,
+    /* Only used while the faction is not played by the player */
+    #[serde(default)]
+    ai_weights: ai::Weights,
+    #[serde(default)]
+    leader: Option<Character>,
+    /* Takes over once the leader dies */
+    #[serde(default)]
+    heir: Option<Character>,
+    /* Found anew at the start of every turn, see trade::find_routes */
+    #[serde(default)]
+    trade_routes: Vec<TradeRoute>,
 }
 
 impl Faction {
-    pub fn new(name: String) -> Self {
+    pub fn from_world(id: String, name: String, culture: String, armies: Vec<army::Army>, settlements: Vec<settlement::Settlement>, fleets: Vec<fleet::Fleet>, treasury: Resources) -> Self {
         Faction {
+            id,
             name,
-            armies: None,
-            settlements: None,
+            culture,
+            armies: Some(armies),
+            settlements: Some(settlements),
+            fleets,
+            treasury,
+            debt_turns: 0,
+            victory_conditions: Vec::new(),
+            visible_regions: Vec::new(),
+            ai_weights: ai::Weights::default(),
+            leader: None,
+            heir: None,
+            trade_routes: Vec::new(),
+        }
+    }
+
+    /* The rebel faction holds no lands to begin with, it gets armies whenever a settlement rises */
+    pub fn rebels() -> Self {
+        let mut rebels = Faction::from_world(REBELS.to_string(), "Rebels".to_string(), String::new(), Vec::new(), Vec::new(), Vec::new(), Resources::default());
+        rebels.ai_weights.aggression = REBEL_AGGRESSION;
+        rebels
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_rebel(&self) -> bool {
+        self.id == REBELS
+    }
+
+    pub fn get_culture(&self) -> &str {
+        &self.culture
+    }
+
+    pub fn get_treasury(&self) -> Resources {
+        self.treasury
+    }
+
+    pub fn get_armies(&self) -> &[army::Army] {
+        self.armies.as_deref().unwrap_or(&[])
+    }
+
+    pub fn get_settlements(&self) -> &[settlement::Settlement] {
+        self.settlements.as_deref().unwrap_or(&[])
+    }
+
+    pub fn get_fleets(&self) -> &[fleet::Fleet] {
+        &self.fleets
+    }
+
+    pub fn get_settlement_mut(&mut self, name: &str) -> Option<&mut settlement::Settlement> {
+        self.settlements.iter_mut().flatten().find(|s| s.get_name() == name)
+    }
+
+    /* Removes a lost settlement, it goes to the faction that took it with add_settlement */
+    pub fn take_settlement(&mut self, name: &str) -> Option<settlement::Settlement> {
+        let settlements = self.settlements.as_mut()?;
+        let index = settlements.iter().position(|s| s.get_name() == name)?;
+        Some(settlements.remove(index))
+    }
+
+    pub fn add_settlement(&mut self, settlement: settlement::Settlement) {
+        self.settlements.get_or_insert_with(Vec::new).push(settlement);
+    }
+
+    /* Food stores of every settlement that is not under siege fill up again */
+    pub fn resupply_settlements(&mut self, map: &Map, besieged: &[String]) {
+        for s in self.settlements.iter_mut().flatten() {
+            if !map.get_settlement_region(s.get_name()).is_some_and(|r| besieged.iter().any(|b| b == r.get_id())) {
+                s.resupply();
+            }
+        }
+    }
+
+    pub fn has_armies_in(&self, region: &str) -> bool {
+        self.armies.iter().flatten().any(|a| a.get_region() == region && a.get_men() > 0)
+    }
+
+    pub fn get_victory_conditions(&self) -> &[Condition] {
+        &self.victory_conditions
+    }
+
+    pub fn set_victory_conditions(&mut self, victory_conditions: Vec<Condition>) {
+        self.victory_conditions = victory_conditions;
+    }
+
+    pub fn can_see(&self, region: &str) -> bool {
+        self.visible_regions.iter().any(|r| r == region)
+    }
+
+    /* Owned regions and their neighbours are always visible, armies see the land around them */
+    pub fn update_visibility(&mut self, map: &Map) {
+        let mut visible: Vec<String> = Vec::new();
+        for region in map.get_regions().iter().filter(|r| r.get_owner() == Some(self.id.as_str())) {
+            visible.extend(map.regions_within(region.get_id(), 1));
+        }
+
+        for a in self.armies.iter().flatten() {
+            let bonus = map.get_region(a.get_region()).and_then(|r| map.get_landscape(r.get_landscape())).map_or(0, |l| l.get_sight_bonus());
+            visible.extend(map.regions_within(a.get_region(), ARMY_SIGHT + bonus));
+        }
+        for f in self.fleets.iter() {
+            visible.extend(map.regions_within(f.get_region(), FLEET_SIGHT));
+        }
+
+        visible.sort();
+        visible.dedup();
+        self.visible_regions = visible;
+    }
+
+    pub fn get_leader(&self) -> Option<&Character> {
+        self.leader.as_ref()
+    }
+
+    pub fn get_heir(&self) -> Option<&Character> {
+        self.heir.as_ref()
+    }
+
+    pub fn set_leader(&mut self, leader: Option<Character>, heir: Option<Character>) {
+        self.leader = leader;
+        self.heir = heir;
+    }
+
+    pub fn get_ai_weights(&self) -> &ai::Weights {
+        &self.ai_weights
+    }
+
+    pub fn set_ai_weights(&mut self, ai_weights: ai::Weights) {
+        self.ai_weights = ai_weights;
+    }
+
+    /* Removes the armies standing in the region, e.g. to fight a battle there */
+    pub fn take_armies_in(&mut self, region: &str) -> Vec<army::Army> {
+        let armies = match &mut self.armies {
+            Some(a) => a,
+            None => return Vec::new(),
+        };
+        let (taken, kept) = armies.drain(..).partition(|a| a.get_region() == region);
+        *armies = kept;
+        taken
+    }
+
+    pub fn return_armies(&mut self, armies: Vec<army::Army>) {
+        self.armies.get_or_insert_with(Vec::new).extend(armies);
+    }
+
+    /* Removes the fleets lying in the region, e.g. to fight a naval battle there */
+    pub fn take_fleets_in(&mut self, region: &str) -> Vec<fleet::Fleet> {
+        let (taken, kept) = self.fleets.drain(..).partition(|f| f.get_region() == region);
+        self.fleets = kept;
+        taken
+    }
+
+    pub fn return_fleets(&mut self, fleets: Vec<fleet::Fleet>) {
+        self.fleets.extend(fleets);
+    }
+
+    /* Ports lose their trade while an enemy fleet holds a sea region next to them that no fleet of
+    the faction or its allies contests. fleets holds the faction id and region of every fleet. */
+    pub fn update_blockades(&mut self, map: &Map, diplomacy: &Diplomacy, fleets: &[(String, String)]) {
+        let id = &self.id;
+        for s in self.settlements.iter_mut().flatten() {
+            let blockaded = match map.get_settlement_region(s.get_name()) {
+                Some(r) if map.is_port(r) => map.get_sea_regions(r).any(|sea| {
+                    let mut owners = fleets.iter().filter(|(_, r)| r == sea.get_id()).map(|(o, _)| o);
+                    owners.clone().any(|o| diplomacy.at_war(id, o)) && !owners.any(|o| diplomacy.has_military_access(id, o))
+                }),
+                _ => false,
+            };
+
+            if blockaded && !s.is_blockaded() {
+                println!("{} is blockaded", s.get_name());
+            }
+            s.set_blockaded(blockaded);
+        }
+    }
+
+    /* Called by the turn manager for every phase of a turn */
+    pub fn run_phase(&mut self, phase: Phase, map: &Map, rules: &Rules, diplomacy: &Diplomacy) {
+        match phase {
+            Phase::Income => self.income_phase(rules),
+            Phase::PlayerOrders => self.orders_phase(),
+            /* AI factions get their orders from ai_phase */
+            Phase::AiFactions => {}
+            Phase::Movement => self.movement_phase(map, diplomacy),
+            Phase::Battles => self.battle_phase(),
+            Phase::EndOfTurn => self.end_of_turn_phase(map, rules),
+        }
+    }
+
+    pub fn income(&self, rules: &Rules) -> Resources {
+        self.settlements.iter().flatten().map(|s| s.production(rules)).sum::<Resources>() + self.trade_income()
+    }
+
+    pub fn get_trade_routes(&self) -> &[TradeRoute] {
+        &self.trade_routes
+    }
+
+    pub fn set_trade_routes(&mut self, routes: Vec<TradeRoute>) {
+        self.trade_routes = routes;
+    }
+
+    /* Gold of all trade routes that are not disrupted */
+    pub fn trade_income(&self) -> Resources {
+        let gold = self.trade_routes.iter().filter(|r| !r.is_disrupted()).map(|r| r.income).sum();
+        Resources { gold, ..Resources::default() }
+    }
+
+    pub fn upkeep(&self, rules: &Rules) -> Resources {
+        let armies: Resources = self.armies.iter().flatten().map(|a| a.upkeep(rules)).sum();
+        let fleets: Resources = self.fleets.iter().map(|f| f.upkeep(rules)).sum();
+        armies + fleets + self.settlements.iter().flatten().map(|s| s.upkeep(rules)).sum()
+    }
+
+    fn income_phase(&mut self, rules: &Rules) {
+        for a in self.armies.iter_mut().flatten() {
+            a.reset_movement_points(rules);
+        }
+        for f in self.fleets.iter_mut() {
+            f.reset_movement_points(rules);
+        }
+        /* Rebels live off the land and are paid by no one */
+        if self.is_rebel() {
+            return;
+        }
+
+        self.treasury += self.income(rules) - self.upkeep(rules);
+        if self.treasury.gold >= 0 && self.treasury.food >= 0 {
+            self.debt_turns = 0;
+            return;
+        }
+
+        /* Unpaid and hungry soldiers lose heart first and desert later */
+        self.debt_turns += 1;
+        for a in self.armies.iter_mut().flatten() {
+            a.change_morale(DEBT_MORALE);
+        }
+
+        if self.debt_turns >= DEBT_TURNS_BEFORE_DISBANDING {
+            let mut armies: Vec<&mut army::Army> = self.armies.iter_mut().flatten().collect();
+            armies.sort_by_key(|a| -a.upkeep(rules).gold);
+            let disbanded = armies.into_iter().find(|a| a.get_men() > 0).and_then(|a| {
+                a.disband_most_expensive_unit(rules);
+                (a.get_men() == 0).then(|| a.get_name().to_string())
+            });
+            /* An army whose last unit went home is gone */
+            if let (Some(name), Some(armies)) = (disbanded, &mut self.armies) {
+                armies.retain(|a| a.get_name() != name);
+            }
+        }
+    }
+
+    fn orders_phase(&mut self) {
+
+    }
+
+    /* Lets the AI plan the turn and carries out its orders. All orders are returned, the diplomatic
+    ones are left for the diplomacy to handle. */
+    pub fn ai_phase(&mut self, ai: &mut dyn Ai, context: &ai::Context) -> Vec<String> {
+        let orders = ai.plan_turn(self, context);
+        for o in orders.iter() {
+            self.retreive_event_code(o, context.map, context.rules, context.diplomacy);
+        }
+        orders
+    }
+
+    fn movement_phase(&mut self, map: &Map, diplomacy: &Diplomacy) {
+        let id = &self.id;
+        for a in self.armies.iter_mut().flatten() {
+            a.advance(map, |r| diplomacy.may_enter(id, r));
+        }
+        for f in self.fleets.iter_mut() {
+            f.advance(map, |r| diplomacy.may_enter(id, r));
+        }
+        self.update_visibility(map);
+    }
+
+    /* Armies out of supply lose men, those that lose their last men are gone. Rebels are fed by
+    the locals. */
+    pub fn suffer_attrition(&mut self, map: &Map, diplomacy: &Diplomacy, season: Season) -> Vec<supply::Loss> {
+        let mut losses: Vec<supply::Loss> = Vec::new();
+        if self.is_rebel() {
+            return losses;
+        }
+        let armies = match &mut self.armies {
+            Some(a) => a,
+            None => return losses,
+        };
+
+        for a in armies.iter_mut().filter(|a| a.get_men() > 0) {
+            let share = supply::attrition(map, diplomacy, &self.id, a.get_region(), season);
+            if share <= 0.0 {
+                continue;
+            }
+            let men = a.lose_share(share);
+            losses.push(supply::Loss { army: a.get_name().to_string(), region: a.get_region().to_string(), men, perished: a.get_men() == 0 });
+        }
+
+        armies.retain(|a| !losses.iter().any(|l| l.perished && l.army == a.get_name()));
+        losses
+    }
+
+    fn battle_phase(&mut self) {
+
+    }
+
+    fn end_of_turn_phase(&mut self, map: &Map, rules: &Rules) {
+        self.advance_recruitment(map, rules);
+        let starving = self.treasury.food < 0;
+        let leader_influence = self.leader.as_ref().map_or(0, |l| l.influence(rules));
+        for s in self.settlements.iter_mut().flatten() {
+            s.advance_construction(rules);
+            s.grow(rules, starving);
+            s.update_public_order(rules, &self.culture, leader_influence);
         }
+        self.update_visibility(map);
     }
 
-    pub fn retreive_event_code(&mut self, code: &str) {
+    /* Governors learn on the job, everyone grows a year older every spring and the dead are
+    replaced. Returns what happened for the turn report. */
+    pub fn characters_turn(&mut self, rules: &Rules, season: Season, rng: &mut impl Rng) -> Vec<String> {
+        let mut report: Vec<String> = Vec::new();
+        let culture = match rules.get_culture(&self.culture) {
+            Some(c) => c,
+            None => return report,
+        };
 
+        for s in self.settlements.iter_mut().flatten() {
+            let settlement = s.get_name().to_string();
+            if let Some(g) = s.get_governor_mut() {
+                if let Some(t) = g.gain_trait(Deed::Governing, rules, rng) {
+                    report.push(format!("{}, governor of {}, is now {}", g.get_name(), settlement, t));
+                }
+            }
+        }
+
+        if season == Season::Spring {
+            let mut offices: Vec<(String, &mut Option<Character>)> = vec![(format!("leader of {}", self.name), &mut self.leader), (format!("heir of {}", self.name), &mut self.heir)];
+            for a in self.armies.iter_mut().flatten() {
+                offices.push((format!("general of {}", a.get_name()), a.get_general_mut()));
+            }
+            for s in self.settlements.iter_mut().flatten() {
+                offices.push((format!("governor of {}", s.get_name()), s.get_governor_mut()));
+            }
+
+            for (office, character) in offices {
+                if !character.as_mut().is_some_and(|c| c.grow_older(culture, rules, rng)) {
+                    continue;
+                }
+                if let Some(c) = character.take() {
+                    report.push(format!("{}, {}, has died at the age of {}", c.get_name(), office, c.get_age()));
+                }
+            }
+        }
+
+        if self.leader.is_none() {
+            if let Some(h) = self.heir.take() {
+                report.push(format!("{} is the new leader of {}", h.get_name(), self.name));
+                self.leader = Some(h);
+            }
+        }
+        self.appoint_characters(rules, rng);
+        report
+    }
+
+    /* Every empty office gets a new character. Leaders are only appointed when there was no heir. */
+    pub fn appoint_characters(&mut self, rules: &Rules, rng: &mut impl Rng) {
+        let culture = match rules.get_culture(&self.culture) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut offices: Vec<&mut Option<Character>> = vec![&mut self.leader, &mut self.heir];
+        offices.extend(self.armies.iter_mut().flatten().filter(|a| a.get_men() > 0).map(|a| a.get_general_mut()));
+        offices.extend(self.settlements.iter_mut().flatten().map(|s| s.get_governor_mut()));
+        for office in offices.into_iter().filter(|o| o.is_none()) {
+            *office = Character::generate(culture, rng);
+        }
+    }
+
+    /* Generals of the armies in the region may earn a trait. Returns the general and trait names. */
+    pub fn reward_generals(&mut self, region: &str, deed: Deed, rules: &Rules, rng: &mut impl Rng) -> Vec<(String, String)> {
+        let mut rewarded: Vec<(String, String)> = Vec::new();
+        for a in self.armies.iter_mut().flatten().filter(|a| a.get_region() == region) {
+            if let Some(g) = a.get_general_mut() {
+                if let Some(t) = g.gain_trait(deed, rules, rng) {
+                    rewarded.push((g.get_name().to_string(), t));
+                }
+            }
+        }
+        rewarded
+    }
+
+    /* Units join the faction's army in the region, or form a new army there */
+    pub fn reinforce(&mut self, name: &str, region: &str, position: (f32, f32), units: Vec<Unit>) {
+        match self.armies.iter_mut().flatten().find(|a| a.get_region() == region) {
+            Some(a) => units.into_iter().for_each(|u| a.add_unit(u)),
+            None => self.raise_army(name, region, position, units),
+        }
+    }
+
+    pub fn change_treasury(&mut self, change: Resources) {
+        self.treasury += change;
+    }
+
+    /* Puts a new army on the map, used for rebels rising in a settlement */
+    pub fn raise_army(&mut self, name: &str, region: &str, position: (f32, f32), units: Vec<Unit>) {
+        let name = self.unique_army_name(name);
+        let army = army::Army::new(name, position, region.to_string(), units, Queue::new());
+        self.armies.get_or_insert_with(Vec::new).push(army);
+    }
+
+    /* Finished units of an army's queue join that army. Units of a settlement's queue join an
+    army in the settlement's region, or form a new one there. */
+    fn advance_recruitment(&mut self, map: &Map, rules: &Rules) {
+        for a in self.armies.iter_mut().flatten() {
+            if let Some(unit_type) = a.get_recruitment_mut().advance().and_then(|t| rules.get_unit_type(&t)) {
+                a.add_unit(Unit::new(unit_type));
+            }
+        }
+
+        let mut raised: Vec<(String, String, Unit)> = Vec::new();
+        for s in self.settlements.iter_mut().flatten() {
+            if let Some(unit_type) = s.get_recruitment_mut().advance().and_then(|t| rules.get_unit_type(&t)) {
+                match map.get_settlement_region(s.get_name()) {
+                    Some(r) => raised.push((s.get_name().to_string(), r.get_id().to_string(), Unit::new(unit_type))),
+                    None => println!("{} is not on the map, {} could not be raised", s.get_name(), unit_type.get_name()),
+                }
+            }
+        }
+
+        for (settlement, region, unit) in raised {
+            match self.armies.iter_mut().flatten().find(|a| a.get_region() == region) {
+                Some(a) => a.add_unit(unit),
+                None => {
+                    let name = self.unique_army_name(&format!("{} Levy", settlement));
+                    let army = army::Army::new(name, (0.0, 0.0), region, vec![unit], Queue::new());
+                    self.armies.get_or_insert_with(Vec::new).push(army);
+                }
+            }
+        }
+    }
+
+    fn unique_army_name(&self, name: &str) -> String {
+        let taken = |n: &str| self.armies.iter().flatten().any(|a| a.get_name() == n);
+        let mut unique = name.to_string();
+        let mut counter = 2;
+        while taken(&unique) {
+            unique = format!("{} {}", name, counter);
+            counter += 1;
+        }
+        unique
+    }
+
+    /* Armies are looked up first, so a settlement can not shadow an army of the same name */
+    fn recruitment_queue_mut(&mut self, name: &str) -> Option<&mut Queue> {
+        if let Some(a) = self.armies.iter_mut().flatten().find(|a| a.get_name() == name) {
+            return Some(a.get_recruitment_mut());
+        }
+        self.settlements.iter_mut().flatten().find(|s| s.get_name() == name).map(|s| s.get_recruitment_mut())
+    }
+
+    /* The settlement itself, or for an army the faction's settlement in the region it stands in */
+    fn settlement_at(&self, place: &str, map: &Map) -> Option<&settlement::Settlement> {
+        let name = match self.armies.iter().flatten().find(|a| a.get_name() == place) {
+            Some(a) => map.get_region(a.get_region())?.get_settlement()?,
+            None => place,
+        };
+        self.settlements.iter().flatten().find(|s| s.get_name() == name)
+    }
+
+    /* Why the unit type can not be recruited at the place right now, None if it can */
+    pub fn recruitment_blocker(&self, unit_type: &UnitType, place: &str, map: &Map) -> Option<String> {
+        if !unit_type.is_available_to(&self.id, &self.culture) {
+            return Some(format!("{} can not recruit {}", self.name, unit_type.get_name()));
+        }
+
+        let buildings = unit_type.get_buildings();
+        if !buildings.is_empty() && !self.settlement_at(place, map).is_some_and(|s| buildings.iter().all(|b| s.has_building(b))) {
+            return Some(format!("{} needs {} to recruit {}", place, buildings.join(", "), unit_type.get_name()));
+        }
+
+        if !self.treasury.can_afford(unit_type.get_cost()) {
+            return Some(format!("{} can not afford {}", self.name, unit_type.get_name()));
+        }
+        None
+    }
+
+    /* Move orders look like "move <region id> <army name>" */
+    fn order_move(&mut self, target: &str, army_name: &str, map: &Map, diplomacy: &Diplomacy) {
+        let id = &self.id;
+        let army = match self.armies.iter_mut().flatten().find(|a| a.get_name() == army_name) {
+            Some(a) => a,
+            None => {
+                println!("Faction {} has no army called {}", id, army_name);
+                return;
+            }
+        };
+
+        match map.find_path(army.get_region(), target, |r| diplomacy.may_enter(id, r)) {
+            Some(p) => army.set_path(p.regions),
+            None => println!("{} can not reach {}", army_name, target),
+        }
+    }
+
+    /* Recruitment orders look like "recruit <unit type id> <army or settlement name>" */
+    fn order_recruit(&mut self, unit_type_id: &str, place: &str, map: &Map, rules: &Rules) {
+        let unit_type = match rules.get_unit_type(unit_type_id) {
+            Some(u) => u,
+            None => {
+                println!("There is no unit type called {}", unit_type_id);
+                return;
+            }
+        };
+
+        if let Some(reason) = self.recruitment_blocker(unit_type, place, map) {
+            println!("{}", reason);
+            return;
+        }
+        let cost = unit_type.get_cost();
+
+        match self.recruitment_queue_mut(place) {
+            Some(q) => q.push(unit_type_id.to_string(), unit_type.get_turns(), cost),
+            None => {
+                println!("Faction {} has no army or settlement called {}", self.id, place);
+                return;
+            }
+        }
+        self.treasury -= cost;
+    }
+
+    /* Cancel orders look like "cancel_recruit <queue index> <army or settlement name>" */
+    fn order_cancel_recruit(&mut self, index: usize, place: &str) {
+        let refund = match self.recruitment_queue_mut(place) {
+            Some(q) => q.cancel(index),
+            None => None,
+        };
+
+        match refund {
+            Some(r) => self.treasury += r,
+            None => println!("{} has no recruitment entry {}", place, index),
+        }
+    }
+
+    /* Upgrade orders look like "upgrade <unit index> <army name>". The army has to stand in a
+    region of its own faction and the difference in cost is paid. */
+    fn order_upgrade(&mut self, index: usize, army_name: &str, map: &Map, rules: &Rules) {
+        let local_buildings: Vec<String> = self.settlement_at(army_name, map).map(|s| s.get_buildings().to_vec()).unwrap_or_default();
+        let (id, culture) = (&self.id, &self.culture);
+        let army = match self.armies.iter_mut().flatten().find(|a| a.get_name() == army_name) {
+            Some(a) => a,
+            None => {
+                println!("Faction {} has no army called {}", id, army_name);
+                return;
+            }
+        };
+
+        if map.get_region(army.get_region()).and_then(|r| r.get_owner()) != Some(id.as_str()) {
+            println!("{} has to be in its own lands to upgrade units", army_name);
+            return;
+        }
+
+        let unit = match army.get_unit_mut(index) {
+            Some(u) => u,
+            None => {
+                println!("{} has no unit {}", army_name, index);
+                return;
+            }
+        };
+
+        let from = rules.unit_type_of(unit);
+        let to = match from.get_upgrade().and_then(|t| rules.get_unit_type(t)) {
+            Some(t) if t.is_available_to(id, culture) => t,
+            _ => {
+                println!("{} can not be upgraded", from.get_name());
+                return;
+            }
+        };
+
+        if let Some(b) = to.get_buildings().iter().find(|b| !local_buildings.contains(b)) {
+            println!("{} needs {} nearby to upgrade to {}", army_name, b, to.get_name());
+            return;
+        }
+
+        let difference = to.get_cost() - from.get_cost();
+        let cost = Resources::new(difference.gold.max(0), difference.food.max(0), difference.manpower.max(0));
+        if !self.treasury.can_afford(cost) {
+            println!("{} can not afford to upgrade to {}", self.name, to.get_name());
+            return;
+        }
+
+        unit.upgrade(from, to);
+        self.treasury -= cost;
+    }
+
+    /* Construction orders look like "build <building id> <settlement name>" */
+    fn order_build(&mut self, building_id: &str, settlement_name: &str, rules: &Rules) {
+        let building = match rules.get_building(building_id) {
+            Some(b) => b,
+            None => {
+                println!("There is no building called {}", building_id);
+                return;
+            }
+        };
+
+        let culture = &self.culture;
+        let settlement = match self.settlements.iter_mut().flatten().find(|s| s.get_name() == settlement_name) {
+            Some(s) => s,
+            None => {
+                println!("Faction {} has no settlement called {}", self.id, settlement_name);
+                return;
+            }
+        };
+
+        if let Some(reason) = settlement.construction_blocker(building, culture) {
+            println!("{}", reason);
+            return;
+        }
+
+        let cost = building.get_cost();
+        if !self.treasury.can_afford(cost) {
+            println!("{} can not afford {}", self.name, building.get_name());
+            return;
+        }
+
+        settlement.get_construction_mut().push(building_id.to_string(), building.get_turns(), cost);
+        self.treasury -= cost;
+    }
+
+    /* Cancel orders look like "cancel_build <queue index> <settlement name>" */
+    fn order_cancel_build(&mut self, index: usize, settlement_name: &str) {
+        let refund = match self.settlements.iter_mut().flatten().find(|s| s.get_name() == settlement_name) {
+            Some(s) => s.get_construction_mut().cancel(index),
+            None => None,
+        };
+
+        match refund {
+            Some(r) => self.treasury += r,
+            None => println!("{} has no construction entry {}", settlement_name, index),
+        }
+    }
+
+    /* Tax orders look like "tax <low|normal|high> <settlement name>" */
+    fn order_tax(&mut self, level: &str, settlement_name: &str) {
+        let tax = match settlement::Tax::from_name(level) {
+            Some(t) => t,
+            None => {
+                println!("There is no tax level called {}", level);
+                return;
+            }
+        };
+
+        match self.settlements.iter_mut().flatten().find(|s| s.get_name() == settlement_name) {
+            Some(s) => s.set_tax(tax),
+            None => println!("Faction {} has no settlement called {}", self.id, settlement_name),
+        }
+    }
+
+    /* Garrison orders look like "garrison <unit index> <army name>". The unit leaves the army for
+    good and joins the garrison of the settlement the army stands in. */
+    fn order_garrison(&mut self, index: usize, army_name: &str, map: &Map) {
+        let army = match self.armies.iter_mut().flatten().find(|a| a.get_name() == army_name) {
+            Some(a) => a,
+            None => {
+                println!("Faction {} has no army called {}", self.id, army_name);
+                return;
+            }
+        };
+
+        let settlement_name = match map.get_region(army.get_region()).and_then(|r| r.get_settlement()) {
+            Some(s) => s,
+            None => {
+                println!("{} is not in a settlement", army_name);
+                return;
+            }
+        };
+        let settlement = match self.settlements.iter_mut().flatten().find(|s| s.get_name() == settlement_name) {
+            Some(s) => s,
+            None => {
+                println!("{} does not belong to {}", settlement_name, self.name);
+                return;
+            }
+        };
+
+        match army.take_unit(index) {
+            Some(u) => settlement.add_to_garrison(u),
+            None => println!("{} has no unit {}", army_name, index),
+        }
+    }
+
+    /* Sail orders look like "sail <region id> <fleet name>" */
+    fn order_sail(&mut self, target: &str, fleet_name: &str, map: &Map, diplomacy: &Diplomacy) {
+        let id = &self.id;
+        let fleet = match self.fleets.iter_mut().find(|f| f.get_name() == fleet_name) {
+            Some(f) => f,
+            None => {
+                println!("Faction {} has no fleet called {}", id, fleet_name);
+                return;
+            }
+        };
+
+        match map.find_sea_path(fleet.get_region(), target, |r| diplomacy.may_enter(id, r)) {
+            Some(p) => fleet.set_path(p.regions),
+            None => println!("{} can not sail to {}", fleet_name, target),
+        }
+    }
+
+    /* Embark orders look like "embark <fleet name>/<army name>", both names may contain spaces. The
+    fleet has to lie in the army's region or in a sea region on its shore. */
+    fn order_embark(&mut self, fleet_name: &str, army_name: &str, map: &Map) {
+        let fleet = match self.fleets.iter_mut().find(|f| f.get_name() == fleet_name) {
+            Some(f) => f,
+            None => {
+                println!("Faction {} has no fleet called {}", self.id, fleet_name);
+                return;
+            }
+        };
+        let armies = self.armies.get_or_insert_with(Vec::new);
+        let army_index = match armies.iter().position(|a| a.get_name() == army_name) {
+            Some(i) => i,
+            None => {
+                println!("Faction {} has no army called {}", self.id, army_name);
+                return;
+            }
+        };
+
+        let army = &mut armies[army_index];
+        let on_shore = army.get_region() == fleet.get_region() || map.get_region(army.get_region()).is_some_and(|r| r.get_sea_neighbours().iter().any(|n| n == fleet.get_region()));
+        if !on_shore {
+            println!("{} is too far away from {}", army_name, fleet.get_name());
+            return;
+        }
+        if army.get_men() > fleet.get_free_space() {
+            println!("{} has no room for {}", fleet.get_name(), army_name);
+            return;
+        }
+        if !army.spend_movement(EMBARK_COST) {
+            println!("{} has no movement left to embark", army_name);
+            return;
+        }
+
+        fleet.embark(armies.remove(army_index));
+    }
+
+    /* Disembark orders look like "disembark <region id> <fleet name>/<army name>". The region has
+    to be the port the fleet lies in or a shore of its sea region. */
+    fn order_disembark(&mut self, target: &str, fleet_name: &str, army_name: &str, map: &Map, diplomacy: &Diplomacy) {
+        let fleet = match self.fleets.iter_mut().find(|f| f.get_name() == fleet_name) {
+            Some(f) => f,
+            None => {
+                println!("Faction {} has no fleet called {}", self.id, fleet_name);
+                return;
+            }
+        };
+        if !fleet.get_armies().iter().any(|a| a.get_name() == army_name) {
+            println!("{} does not carry {}", fleet_name, army_name);
+            return;
+        }
+
+        let region = match map.get_region(target) {
+            Some(r) => r,
+            None => {
+                println!("There is no region called {}", target);
+                return;
+            }
+        };
+        let on_shore = target == fleet.get_region() || region.get_sea_neighbours().iter().any(|n| n == fleet.get_region());
+        if !on_shore || map.entry_cost(region).is_none() || !diplomacy.may_enter(&self.id, region) {
+            println!("{} can not land in {}", army_name, target);
+            return;
+        }
+
+        let mut army = fleet.disembark(army_name).expect("Army was just found in the fleet");
+        if !army.spend_movement(DISEMBARK_COST) {
+            println!("{} has no movement left to disembark", army_name);
+            fleet.embark(army);
+            return;
+        }
+        army.set_region(region);
+        self.armies.get_or_insert_with(Vec::new).push(army);
+    }
+
+    pub fn retreive_event_code(&mut self, code: &str, map: &Map, rules: &Rules, diplomacy: &Diplomacy) {
+        let mut split_string = code.splitn(3, ' ');
+        let command = split_string.next().unwrap_or("");
+        let argument = split_string.next();
+        let target = split_string.next();
+
+        match (command, argument, target) {
+            ("move", Some(region), Some(army_name)) => self.order_move(region, army_name, map, diplomacy),
+            ("recruit", Some(unit_type), Some(place)) => self.order_recruit(unit_type, place, map, rules),
+            ("cancel_recruit", Some(index), Some(place)) => {
+                let index: usize = index.parse().expect("Not a valid index");
+                self.order_cancel_recruit(index, place);
+            }
+            ("upgrade", Some(index), Some(army_name)) => {
+                let index: usize = index.parse().expect("Not a valid index");
+                self.order_upgrade(index, army_name, map, rules);
+            }
+            ("garrison", Some(index), Some(army_name)) => {
+                let index: usize = index.parse().expect("Not a valid index");
+                self.order_garrison(index, army_name, map);
+            }
+            ("sail", Some(region), Some(fleet_name)) => self.order_sail(region, fleet_name, map, diplomacy),
+            ("embark", Some(_), _) => match code["embark ".len()..].split_once('/') {
+                Some((fleet_name, army_name)) => self.order_embark(fleet_name, army_name, map),
+                None => println!("Embark orders name the fleet and the army: {}", code),
+            },
+            ("disembark", Some(region), Some(names)) => match names.split_once('/') {
+                Some((fleet_name, army_name)) => self.order_disembark(region, fleet_name, army_name, map, diplomacy),
+                None => println!("Disembark orders name the fleet and the army: {}", code),
+            },
+            ("build", Some(building), Some(settlement)) => self.order_build(building, settlement, rules),
+            ("tax", Some(level), Some(settlement)) => self.order_tax(level, settlement),
+            ("cancel_build", Some(index), Some(settlement)) => {
+                let index: usize = index.parse().expect("Not a valid index");
+                self.order_cancel_build(index, settlement);
+            }
+            ("move", _, _) | ("recruit", _, _) | ("cancel_recruit", _, _) | ("upgrade", _, _) | ("garrison", _, _) | ("sail", _, _) | ("embark", _, _) | ("disembark", _, _) | ("build", _, _) | ("cancel_build", _, _) | ("tax", _, _) => panic!("Order is missing parameters: {}", code),
+            _ => {}
+        }
     }
 }
@@ -1,14 +1,289 @@
 use serde::{Deserialize, Serialize};
 
-mod unit;
+use crate::game::map::{region::Region, Map};
+use crate::game::queue::Queue;
+use crate::game::resources::Resources;
+use crate::game::rules::Rules;
+
+use super::character::{self, Character};
+
+pub(crate) mod unit;
+
+/* Movement points of an army without units */
+const MOVEMENT_POINTS: u32 = 6;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Army {
+    name: String,
+    position: (f32, f32),
+    /* Id of the map region the army stands in */
+    region: String,
     units: Option<Vec<unit::Unit>>,
+    movement_points: u32,
+    /* Regions still to walk through for the current move order */
+    path: Vec<String>,
+    /* Units raised in the field join this army directly */
+    #[serde(default)]
+    recruitment: Queue,
+    #[serde(default)]
+    general: Option<Character>,
 }
 
 impl Army {
-    pub fn new() -> Self {
-        Army { units: None }
+    pub fn new(name: String, position: (f32, f32), region: String, units: Vec<unit::Unit>, recruitment: Queue) -> Self {
+        Army {
+            name,
+            position,
+            region,
+            units: Some(units),
+            movement_points: MOVEMENT_POINTS,
+            path: Vec::new(),
+            recruitment,
+            general: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn add_unit(&mut self, unit: unit::Unit) {
+        self.units.get_or_insert_with(Vec::new).push(unit);
+    }
+
+    pub fn get_recruitment(&self) -> &Queue {
+        &self.recruitment
+    }
+
+    pub fn get_recruitment_mut(&mut self) -> &mut Queue {
+        &mut self.recruitment
+    }
+
+    /* The army is drawn at the position of the region it enters */
+    pub fn set_region(&mut self, region: &Region) {
+        self.region = region.get_id().to_string();
+        self.position = region.get_position();
+        self.path.clear();
+    }
+
+    /* Aboard a fleet the army only goes where the fleet goes, it is drawn with the fleet */
+    pub fn board(&mut self, region: String) {
+        self.region = region;
+        self.path.clear();
+    }
+
+    pub fn get_men(&self) -> u32 {
+        self.units.iter().flatten().map(|u| u.get_men()).sum()
+    }
+
+    pub fn get_units(&self) -> &[unit::Unit] {
+        self.units.as_deref().unwrap_or(&[])
+    }
+
+    pub fn into_units(self) -> Vec<unit::Unit> {
+        self.units.unwrap_or_default()
+    }
+
+    pub fn get_unit_mut(&mut self, index: usize) -> Option<&mut unit::Unit> {
+        self.units.as_mut()?.get_mut(index)
+    }
+
+    pub fn take_unit(&mut self, index: usize) -> Option<unit::Unit> {
+        let units = self.units.as_mut()?;
+        if index < units.len() {
+            Some(units.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /* Every unit loses the same share of its men */
+    pub fn lose_share(&mut self, share: f32) -> u32 {
+        self.units.iter_mut().flatten().map(|u| u.lose_share(share)).sum()
+    }
+
+    /* Pays for an action like embarking, false if the army has not enough movement points left */
+    pub fn spend_movement(&mut self, cost: u32) -> bool {
+        if self.movement_points < cost {
+            return false;
+        }
+        self.movement_points -= cost;
+        true
+    }
+
+    pub fn upkeep(&self, rules: &Rules) -> Resources {
+        self.units.iter().flatten().map(|u| rules.unit_type_of(u).get_upkeep()).sum()
+    }
+
+    /* Sends the unit with the highest upkeep home. Returns false if there was nothing to disband. */
+    pub fn disband_most_expensive_unit(&mut self, rules: &Rules) -> bool {
+        let units = match &mut self.units {
+            Some(u) if !u.is_empty() => u,
+            _ => return false,
+        };
+
+        let mut index = 0;
+        for (i, u) in units.iter().enumerate() {
+            if rules.unit_type_of(u).get_upkeep().gold > rules.unit_type_of(&units[index]).get_upkeep().gold {
+                index = i;
+            }
+        }
+        units.remove(index);
+        true
+    }
+
+    /* A good general makes the same men fight better */
+    pub fn power(&self, rules: &Rules) -> f32 {
+        let command = self.general.as_ref().map_or(0, |g| g.command(rules));
+        let power: f32 = self.units.iter().flatten().map(|u| u.power(rules.unit_type_of(u))).sum();
+        power * (1.0 + command as f32 * character::COMMAND_BONUS)
+    }
+
+    pub fn get_general(&self) -> Option<&Character> {
+        self.general.as_ref()
+    }
+
+    pub fn get_general_mut(&mut self) -> &mut Option<Character> {
+        &mut self.general
+    }
+
+    /* Average morale weighted by the men of each unit */
+    pub fn get_morale(&self) -> u32 {
+        let men = self.get_men();
+        if men == 0 {
+            return 0;
+        }
+        let total: u32 = self.units.iter().flatten().map(|u| u.get_men() * u.get_morale()).sum();
+        total / men
+    }
+
+    /* Spreads the damage over the units by their size. Units without men are removed. Returns
+    how many men were lost. */
+    pub fn take_damage(&mut self, damage: f32, rules: &Rules) -> u32 {
+        let total = self.get_men();
+        if total == 0 {
+            return 0;
+        }
+
+        let mut lost = 0;
+        for u in self.units.iter_mut().flatten() {
+            let share = damage * u.get_men() as f32 / total as f32;
+            lost += u.take_damage(share, rules.unit_type_of(u));
+        }
+
+        if let Some(units) = &mut self.units {
+            units.retain(|u| u.get_men() > 0);
+        }
+        lost
+    }
+
+    pub fn change_morale(&mut self, change: i32) {
+        for u in self.units.iter_mut().flatten() {
+            u.change_morale(change);
+        }
+    }
+
+    pub fn gain_experience(&mut self, experience: u32) {
+        for u in self.units.iter_mut().flatten() {
+            u.gain_experience(experience);
+        }
+    }
+
+    /* The slowest unit sets the pace */
+    pub fn reset_movement_points(&mut self, rules: &Rules) {
+        self.movement_points = self.units.iter().flatten().map(|u| rules.unit_type_of(u).get_speed()).min().unwrap_or(MOVEMENT_POINTS);
+    }
+
+    pub fn set_path(&mut self, path: Vec<String>) {
+        self.path = path;
+    }
+
+    /* Walks along the path until the army runs out of movement points. The order is dropped if
+    a region on the way can no longer be crossed. */
+    pub fn advance<F: Fn(&Region) -> bool>(&mut self, map: &Map, may_cross: F) {
+        while let Some(next) = self.path.first() {
+            let region = match map.get_region(next) {
+                Some(r) => r,
+                None => {
+                    self.path.clear();
+                    return;
+                }
+            };
+
+            let cost = match map.entry_cost(region) {
+                Some(c) if may_cross(region) => c,
+                _ => {
+                    self.path.clear();
+                    return;
+                }
+            };
+
+            if cost > self.movement_points {
+                return;
+            }
+
+            self.movement_points -= cost;
+            self.position = region.get_position();
+            self.region = self.path.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /* Five plains regions in a row, each costs two movement points to enter */
+    fn road() -> Map {
+        let ids = ["a", "b", "c", "d", "e"];
+        let regions: Vec<serde_json::Value> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let neighbours: Vec<&str> = ids.iter().enumerate().filter(|(j, _)| i.abs_diff(*j) == 1).map(|(_, n)| *n).collect();
+                json!({"id": id, "name": id, "owner": null, "landscape": "plains", "settlement": null, "position": [i as f32, 0.0], "land_neighbours": neighbours, "sea_neighbours": []})
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "landscapes": [{"name": "plains", "movement_cost": 2, "passable": true, "defence_bonus": 0.0}],
+            "regions": regions
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn advance_stops_when_movement_runs_out() {
+        let map = road();
+        let rules = Rules::new(Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        let mut army = Army::new("Host".to_string(), (0.0, 0.0), "a".to_string(), Vec::new(), Queue::new());
+        army.set_path(map.find_path("a", "e", |_| true).unwrap().regions);
+
+        army.advance(&map, |_| true);
+        assert_eq!(army.get_region(), "d");
+        assert_eq!(army.position, (3.0, 0.0));
+        assert_eq!(army.path, vec!["e"]);
+        assert_eq!(army.movement_points, 0);
+
+        army.reset_movement_points(&rules);
+        army.advance(&map, |_| true);
+        assert_eq!(army.get_region(), "e");
+        assert!(army.path.is_empty());
+    }
+
+    #[test]
+    fn advance_drops_a_blocked_path() {
+        let map = road();
+        let mut army = Army::new("Host".to_string(), (0.0, 0.0), "a".to_string(), Vec::new(), Queue::new());
+        army.set_path(vec!["b".to_string(), "c".to_string()]);
+
+        army.advance(&map, |r| r.get_id() != "c");
+        assert_eq!(army.get_region(), "b");
+        assert!(army.path.is_empty());
     }
 }
@@ -1,10 +1,85 @@
 use serde::{Deserialize, Serialize};
 
+pub(crate) mod unit_type;
+
+use unit_type::UnitType;
+
+/* Experience needed for every veterancy level */
+const EXPERIENCE_PER_LEVEL: u32 = 3;
+const MAX_VETERANCY: u32 = 3;
+/* Each veterancy level makes a unit this much stronger and tougher */
+const VETERANCY_BONUS: f32 = 0.1;
+
 #[derive(Serialize, Deserialize, Clone)]
-pub struct Unit {}
+pub struct Unit {
+    /* Id of the unit type, all stats come from there */
+    unit_type: String,
+    men: u32,
+    experience: u32,
+    veterancy: u32,
+    /* 0 to 100, a unit fights worse the lower it gets */
+    morale: u32,
+}
 
 impl Unit {
-    pub fn new() -> Self {
-        Unit {}
+    pub fn new(unit_type: &UnitType) -> Self {
+        Unit {
+            unit_type: unit_type.get_id().to_string(),
+            men: unit_type.get_men(),
+            experience: 0,
+            veterancy: 0,
+            morale: unit_type.get_morale().min(100),
+        }
+    }
+
+    pub fn get_unit_type(&self) -> &str {
+        &self.unit_type
+    }
+
+    pub fn get_men(&self) -> u32 {
+        self.men
+    }
+
+    pub fn get_morale(&self) -> u32 {
+        self.morale
+    }
+
+    fn veterancy_factor(&self) -> f32 {
+        1.0 + self.veterancy as f32 * VETERANCY_BONUS
+    }
+
+    pub fn power(&self, unit_type: &UnitType) -> f32 {
+        let fighting = (unit_type.get_attack() + unit_type.get_defence()) as f32 / 2.0;
+        self.men as f32 * fighting * self.veterancy_factor() * (0.5 + self.morale as f32 / 200.0)
+    }
+
+    /* Turns damage into fallen men. Returns how many men were lost. */
+    pub fn take_damage(&mut self, damage: f32, unit_type: &UnitType) -> u32 {
+        let toughness = unit_type.get_health().max(1) as f32 * self.veterancy_factor();
+        let lost = ((damage / toughness).round() as u32).min(self.men);
+        self.men -= lost;
+        lost
+    }
+
+    /* Hunger, sinking ships and the like kill a share of the men, at least one while any are left */
+    pub fn lose_share(&mut self, share: f32) -> u32 {
+        let lost = ((self.men as f32 * share).ceil() as u32).min(self.men);
+        self.men -= lost;
+        lost
+    }
+
+    pub fn change_morale(&mut self, change: i32) {
+        self.morale = (self.morale as i32 + change).clamp(0, 100) as u32;
+    }
+
+    pub fn gain_experience(&mut self, experience: u32) {
+        self.experience += experience;
+        self.veterancy = (self.experience / EXPERIENCE_PER_LEVEL).min(MAX_VETERANCY);
+    }
+
+    /* Keeps experience and the share of men the unit had left */
+    pub fn upgrade(&mut self, from: &UnitType, to: &UnitType) {
+        self.men = (self.men as u64 * to.get_men() as u64 / from.get_men().max(1) as u64) as u32;
+        self.unit_type = to.get_id().to_string();
     }
 }
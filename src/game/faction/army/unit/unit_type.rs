@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::resources::Resources;
+
+/* Definition of a kind of unit, loaded from data/units */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnitType {
+    /* File stem of the unit file, used by recruitment orders and army files */
+    #[serde(default)]
+    id: String,
+    name: String,
+    /* Men of a fresh unit */
+    men: u32,
+    attack: u32,
+    defence: u32,
+    /* Hits a single man takes before he falls */
+    health: u32,
+    /* Morale a fresh unit starts with, at most 100 */
+    morale: u32,
+    /* Movement points per turn, the slowest unit sets the pace of its army */
+    speed: u32,
+    /* Paid in full when the unit is queued */
+    cost: Resources,
+    /* Paid every turn during the income phase */
+    upkeep: Resources,
+    /* Turns the unit spends in the recruitment queue */
+    turns: u32,
+    /* Faction ids and cultures allowed to field the unit, empty lists allow everyone */
+    #[serde(default)]
+    factions: Vec<String>,
+    #[serde(default)]
+    cultures: Vec<String>,
+    /* Unit type id the unit can be upgraded to */
+    #[serde(default)]
+    upgrade: Option<String>,
+    /* Building ids the recruiting settlement needs */
+    #[serde(default)]
+    buildings: Vec<String>,
+}
+
+impl UnitType {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_men(&self) -> u32 {
+        self.men
+    }
+
+    pub fn get_attack(&self) -> u32 {
+        self.attack
+    }
+
+    pub fn get_defence(&self) -> u32 {
+        self.defence
+    }
+
+    pub fn get_health(&self) -> u32 {
+        self.health
+    }
+
+    pub fn get_morale(&self) -> u32 {
+        self.morale
+    }
+
+    pub fn get_speed(&self) -> u32 {
+        self.speed
+    }
+
+    pub fn get_cost(&self) -> Resources {
+        self.cost
+    }
+
+    pub fn get_upkeep(&self) -> Resources {
+        self.upkeep
+    }
+
+    pub fn get_turns(&self) -> u32 {
+        self.turns
+    }
+
+    pub fn get_upgrade(&self) -> Option<&str> {
+        self.upgrade.as_deref()
+    }
+
+    pub fn get_buildings(&self) -> &[String] {
+        &self.buildings
+    }
+
+    pub fn is_available_to(&self, faction: &str, culture: &str) -> bool {
+        (self.factions.is_empty() || self.factions.iter().any(|f| f == faction))
+            && (self.cultures.is_empty() || self.cultures.iter().any(|c| c == culture))
+    }
+}
@@ -0,0 +1,123 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::rules::Rules;
+
+use character_trait::Deed;
+use culture::Culture;
+
+pub(crate) mod character_trait;
+pub(crate) mod culture;
+
+/* Power of an army grows by this share for every point of its general's command */
+pub const COMMAND_BONUS: f32 = 0.05;
+/* Gold and food of a settlement grow by this share for every point of its governor's management */
+pub const MANAGEMENT_BONUS: f32 = 0.05;
+/* Public order for every point of influence */
+pub const INFLUENCE_ORDER: i32 = 2;
+/* Range of the stats and the age new characters start with */
+const MIN_STAT: u32 = 1;
+const MAX_STAT: u32 = 5;
+const MIN_AGE: u32 = 20;
+const MAX_AGE: u32 = 40;
+/* Chance to die every year for every year beyond the culture's old age */
+const DEATH_CHANCE_PER_YEAR: f32 = 0.02;
+
+/* A named general, governor, leader or heir */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Character {
+    name: String,
+    /* In years, one passes every spring */
+    age: u32,
+    /* Leading armies in battle */
+    #[serde(default)]
+    command: u32,
+    /* Running a settlement */
+    #[serde(default)]
+    management: u32,
+    /* Keeping the people content */
+    #[serde(default)]
+    influence: u32,
+    /* Trait ids */
+    #[serde(default)]
+    traits: Vec<String>,
+}
+
+impl Character {
+    /* A new character of the culture with random stats, None if the culture has no names */
+    pub fn generate(culture: &Culture, rng: &mut impl Rng) -> Option<Self> {
+        let names = culture.get_names();
+        if names.is_empty() {
+            return None;
+        }
+
+        Some(Character {
+            name: names[rng.gen_range(0..names.len())].clone(),
+            age: rng.gen_range(MIN_AGE..=MAX_AGE),
+            command: rng.gen_range(MIN_STAT..=MAX_STAT),
+            management: rng.gen_range(MIN_STAT..=MAX_STAT),
+            influence: rng.gen_range(MIN_STAT..=MAX_STAT),
+            traits: Vec::new(),
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_age(&self) -> u32 {
+        self.age
+    }
+
+    pub fn get_traits(&self) -> &[String] {
+        &self.traits
+    }
+
+    /* Stats with the modifiers of all traits, they never drop below zero */
+    fn stat(&self, base: u32, rules: &Rules, modifier: fn(&character_trait::Trait) -> i32) -> u32 {
+        let traits: i32 = self.traits.iter().filter_map(|t| rules.get_trait(t)).map(modifier).sum();
+        (base as i32 + traits).max(0) as u32
+    }
+
+    pub fn command(&self, rules: &Rules) -> u32 {
+        self.stat(self.command, rules, |t| t.get_command())
+    }
+
+    pub fn management(&self, rules: &Rules) -> u32 {
+        self.stat(self.management, rules, |t| t.get_management())
+    }
+
+    pub fn influence(&self, rules: &Rules) -> u32 {
+        self.stat(self.influence, rules, |t| t.get_influence())
+    }
+
+    /* Rolls for every trait the deed can earn, returns the name of the one gained. Traits the
+    character has or that clash with its traits are left out. */
+    pub fn gain_trait(&mut self, deed: Deed, rules: &Rules, rng: &mut impl Rng) -> Option<String> {
+        let has = |id: &str| self.traits.iter().any(|t| t == id);
+        let clashes = |id: &str| self.traits.iter().filter_map(|t| rules.get_trait(t)).any(|t| t.get_excludes().iter().any(|e| e == id));
+
+        let candidates: Vec<&character_trait::Trait> = rules
+            .get_traits()
+            .iter()
+            .filter(|t| t.get_gained_from() == Some(deed) && !has(t.get_id()) && !clashes(t.get_id()))
+            .filter(|t| !t.get_excludes().iter().any(|e| has(e)))
+            .collect();
+
+        let gained = candidates.into_iter().find(|t| rng.gen::<f32>() < t.get_chance())?;
+        self.traits.push(gained.get_id().to_string());
+        Some(gained.get_name().to_string())
+    }
+
+    /* Adds a year, returns true if the character died of old age */
+    pub fn grow_older(&mut self, culture: &Culture, rules: &Rules, rng: &mut impl Rng) -> bool {
+        self.age += 1;
+        if self.traits.iter().filter_map(|t| rules.get_trait(t)).any(|t| t.is_ageless()) {
+            return false;
+        }
+        match culture.get_old_age() {
+            Some(old_age) if self.age > old_age => rng.gen::<f32>() < (self.age - old_age) as f32 * DEATH_CHANCE_PER_YEAR,
+            _ => false,
+        }
+    }
+}
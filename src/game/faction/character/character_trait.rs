@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/* Deeds that may earn a character a trait */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Deed {
+    BattleWon,
+    BattleLost,
+    SettlementCaptured,
+    /* Every turn spent governing a settlement */
+    Governing,
+}
+
+/* Definition of a character trait, loaded from data/traits */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Trait {
+    /* File stem of the trait file, used by character entries in the world files */
+    #[serde(default)]
+    id: String,
+    name: String,
+    /* Added to the character's stats, may be negative */
+    #[serde(default)]
+    command: i32,
+    #[serde(default)]
+    management: i32,
+    #[serde(default)]
+    influence: i32,
+    /* Traits without a deed are only handed out by the world files */
+    #[serde(default)]
+    gained_from: Option<Deed>,
+    /* Chance to gain the trait every time the deed is done */
+    #[serde(default)]
+    chance: f32,
+    /* Trait ids a character with this trait can not have */
+    #[serde(default)]
+    excludes: Vec<String>,
+    /* The character never dies of old age */
+    #[serde(default)]
+    ageless: bool,
+}
+
+impl Trait {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_command(&self) -> i32 {
+        self.command
+    }
+
+    pub fn get_management(&self) -> i32 {
+        self.management
+    }
+
+    pub fn get_influence(&self) -> i32 {
+        self.influence
+    }
+
+    pub fn get_gained_from(&self) -> Option<Deed> {
+        self.gained_from
+    }
+
+    pub fn get_chance(&self) -> f32 {
+        self.chance
+    }
+
+    pub fn get_excludes(&self) -> &[String] {
+        &self.excludes
+    }
+
+    pub fn is_ageless(&self) -> bool {
+        self.ageless
+    }
+}
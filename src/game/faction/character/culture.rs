@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/* How the characters of a culture are named and how long they live, loaded from data/cultures */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Culture {
+    /* File stem of the culture file, the culture entry of the faction files */
+    #[serde(default)]
+    id: String,
+    /* New characters get one of these */
+    names: Vec<String>,
+    /* Age from which characters may die every year, cultures without one never die of old age */
+    #[serde(default)]
+    old_age: Option<u32>,
+}
+
+impl Culture {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get_old_age(&self) -> Option<u32> {
+        self.old_age
+    }
+}
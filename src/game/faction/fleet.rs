@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::map::{region::Region, Map};
+use crate::game::resources::Resources;
+use crate::game::rules::Rules;
+
+use super::army::Army;
+
+const MOVEMENT_POINTS: u32 = 8;
+/* Men a single ship carries */
+pub const MEN_PER_SHIP: u32 = 100;
+const SHIP_UPKEEP: Resources = Resources { gold: 3, food: 1, manpower: 0 };
+
+/* Ships of one faction sailing together. Embarked armies travel with the fleet and can only
+leave it on a shore next to the fleet. */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Fleet {
+    name: String,
+    /* Id of the sea region or port the fleet lies in */
+    region: String,
+    ships: u32,
+    movement_points: u32,
+    path: Vec<String>,
+    #[serde(default)]
+    armies: Vec<Army>,
+}
+
+impl Fleet {
+    pub fn new(name: String, region: String, ships: u32) -> Self {
+        Fleet {
+            name,
+            region,
+            ships,
+            movement_points: MOVEMENT_POINTS,
+            path: Vec::new(),
+            armies: Vec::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn get_ships(&self) -> u32 {
+        self.ships
+    }
+
+    pub fn get_armies(&self) -> &[Army] {
+        &self.armies
+    }
+
+    pub fn set_region(&mut self, region: String) {
+        for a in self.armies.iter_mut() {
+            a.board(region.clone());
+        }
+        self.region = region;
+        self.path.clear();
+    }
+
+    /* Men that can still come aboard */
+    pub fn get_free_space(&self) -> u32 {
+        let carried: u32 = self.armies.iter().map(|a| a.get_men()).sum();
+        (self.ships * MEN_PER_SHIP).saturating_sub(carried)
+    }
+
+    pub fn embark(&mut self, mut army: Army) {
+        army.board(self.region.clone());
+        self.armies.push(army);
+    }
+
+    pub fn disembark(&mut self, army_name: &str) -> Option<Army> {
+        let index = self.armies.iter().position(|a| a.get_name() == army_name)?;
+        Some(self.armies.remove(index))
+    }
+
+    pub fn upkeep(&self, rules: &Rules) -> Resources {
+        let crews = Resources::new(SHIP_UPKEEP.gold * self.ships as i32, SHIP_UPKEEP.food * self.ships as i32, 0);
+        crews + self.armies.iter().map(|a| a.upkeep(rules)).sum()
+    }
+
+    /* Lost ships take their share of the embarked armies down with them. Returns the men that drowned. */
+    pub fn sink(&mut self, ships: u32) -> u32 {
+        let ships = ships.min(self.ships);
+        if ships == 0 {
+            return 0;
+        }
+
+        let share = ships as f32 / self.ships as f32;
+        self.ships -= ships;
+        if self.ships == 0 {
+            let drowned = self.armies.iter().map(|a| a.get_men()).sum();
+            self.armies.clear();
+            return drowned;
+        }
+        self.armies.iter_mut().map(|a| a.lose_share(share)).sum()
+    }
+
+    pub fn reset_movement_points(&mut self, rules: &Rules) {
+        self.movement_points = MOVEMENT_POINTS;
+        for a in self.armies.iter_mut() {
+            a.reset_movement_points(rules);
+        }
+    }
+
+    pub fn set_path(&mut self, path: Vec<String>) {
+        self.path = path;
+    }
+
+    /* Sails along the path like Army::advance walks, the embarked armies move along */
+    pub fn advance<F: Fn(&Region) -> bool>(&mut self, map: &Map, may_cross: F) {
+        while let Some(next) = self.path.first() {
+            let cost = match map.get_region(next) {
+                Some(r) if may_cross(r) => map.sailing_cost(r),
+                _ => None,
+            };
+            let cost = match cost {
+                Some(c) => c,
+                None => {
+                    self.path.clear();
+                    return;
+                }
+            };
+
+            if cost > self.movement_points {
+                return;
+            }
+
+            self.movement_points -= cost;
+            self.region = self.path.remove(0);
+            for a in self.armies.iter_mut() {
+                a.board(self.region.clone());
+            }
+        }
+    }
+}
@@ -1,14 +1,388 @@
 use serde::{Deserialize, Serialize};
 
-mod building;
+use crate::game::queue::Queue;
+use crate::game::resources::Resources;
+use crate::game::rules::Rules;
+
+use super::army::{unit::Unit, Army};
+use super::character::{self, Character};
+use building::Building;
+
+pub(crate) mod building;
+
+/* What a settlement yields without any buildings */
+const BASE_PRODUCTION: Resources = Resources { gold: 20, food: 10, manpower: 5 };
+/* Turns of food a settlement stores, every level adds SUPPLIES_PER_LEVEL more */
+const BASE_SUPPLIES: u32 = 2;
+const SUPPLIES_PER_LEVEL: u32 = 2;
+/* Share of the garrison that dies each turn once the food has run out */
+const STARVATION: f32 = 0.15;
+const STARVATION_MORALE: i32 = -10;
+/* People a settlement can hold for every level, buildings add housing on top */
+const POPULATION_PER_LEVEL: u32 = 2000;
+/* Share the population grows by every turn, every point of food the settlement produces adds
+FOOD_GROWTH to it. A starving faction's settlements shrink instead. */
+const BASE_GROWTH: f32 = 0.01;
+const FOOD_GROWTH: f32 = 0.001;
+const FAMINE: f32 = 0.03;
+/* Every this many people add one manpower to the production */
+const PEOPLE_PER_MANPOWER: u32 = 200;
+/* Every this many people add one point to what the settlement is worth to traders */
+const PEOPLE_PER_TRADE: u32 = 100;
+/* Public order without any modifiers, it runs from 0 to 100 */
+const BASE_ORDER: i32 = 60;
+/* How far the public order moves towards its target value in one turn */
+const ORDER_DRIFT: i32 = 10;
+/* Every this many men in the garrison add one point of order, up to MAX_GARRISON_ORDER */
+const MEN_PER_ORDER: u32 = 20;
+const MAX_GARRISON_ORDER: i32 = 20;
+/* People of another culture than their ruler's */
+const FOREIGN_RULE_ORDER: i32 = -25;
+/* A conquered settlement stays restless for CONQUEST_TURNS, every turn left costs some order */
+const CONQUEST_TURNS: u32 = 10;
+const CONQUEST_ORDER: i32 = -3;
+/* Every thousand people cost this much order */
+const CROWDING_ORDER: i32 = -2;
+/* Below this the people take up arms against their ruler */
+pub const REBELLION_ORDER: i32 = 20;
+/* Below this the ruler is warned about the unrest */
+pub const UNREST_ORDER: i32 = 40;
+
+/* How hard a settlement's people are taxed, higher taxes bring in gold but cost order */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Tax {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Tax {
+    pub fn from_name(name: &str) -> Option<Tax> {
+        match name {
+            "low" => Some(Tax::Low),
+            "normal" => Some(Tax::Normal),
+            "high" => Some(Tax::High),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Tax::Low => "low",
+            Tax::Normal => "normal",
+            Tax::High => "high",
+        }
+    }
+
+    fn gold_factor(self) -> f32 {
+        match self {
+            Tax::Low => 0.5,
+            Tax::Normal => 1.0,
+            Tax::High => 1.5,
+        }
+    }
+
+    fn public_order(self) -> i32 {
+        match self {
+            Tax::Low => 15,
+            Tax::Normal => 0,
+            Tax::High => -20,
+        }
+    }
+}
+
+fn default_public_order() -> i32 {
+    BASE_ORDER
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Settlement {
-    buildings: Option<Vec<building::Building>>,
+    name: String,
+    /* Building ids */
+    buildings: Option<Vec<String>>,
+    /* Some buildings can only be put up in larger settlements */
+    level: u32,
+    /* Units raised here join an army in the settlement's region */
+    #[serde(default)]
+    recruitment: Queue,
+    #[serde(default)]
+    construction: Queue,
+    /* Units that only defend the walls, they never leave the settlement */
+    #[serde(default)]
+    garrison: Vec<Unit>,
+    /* Turns the garrison can hold out once the settlement is besieged */
+    #[serde(default)]
+    supplies: u32,
+    /* Enemy ships keep the merchants out of the port */
+    #[serde(default)]
+    blockaded: bool,
+    #[serde(default)]
+    population: u32,
+    #[serde(default = "default_public_order")]
+    public_order: i32,
+    #[serde(default)]
+    tax: Tax,
+    /* Culture of the people living here, which may not be the one of the owner */
+    #[serde(default)]
+    culture: String,
+    /* Turns the settlement is still upset about having been conquered */
+    #[serde(default)]
+    conquest_turns: u32,
+    #[serde(default)]
+    governor: Option<Character>,
 }
 
 impl Settlement {
-    pub fn new() -> Self {
-        Settlement { buildings: None }
+    pub fn new(name: String, buildings: Vec<String>, level: u32, garrison: Vec<Unit>, population: u32, culture: String) -> Self {
+        Settlement {
+            name,
+            buildings: Some(buildings),
+            level,
+            recruitment: Queue::new(),
+            construction: Queue::new(),
+            garrison,
+            supplies: BASE_SUPPLIES + level * SUPPLIES_PER_LEVEL,
+            blockaded: false,
+            population,
+            public_order: BASE_ORDER,
+            tax: Tax::Normal,
+            culture,
+            conquest_turns: 0,
+            governor: None,
+        }
+    }
+
+    /* Settlements without a population in their file start half full */
+    pub fn default_population(level: u32) -> u32 {
+        level * POPULATION_PER_LEVEL / 2
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_buildings(&self) -> &[String] {
+        self.buildings.as_deref().unwrap_or(&[])
+    }
+
+    pub fn has_building(&self, building: &str) -> bool {
+        self.get_buildings().iter().any(|b| b == building)
+    }
+
+    pub fn get_recruitment(&self) -> &Queue {
+        &self.recruitment
+    }
+
+    pub fn get_recruitment_mut(&mut self) -> &mut Queue {
+        &mut self.recruitment
+    }
+
+    pub fn get_construction(&self) -> &Queue {
+        &self.construction
+    }
+
+    pub fn get_construction_mut(&mut self) -> &mut Queue {
+        &mut self.construction
+    }
+
+    /* Why the building can not be started here, None if every prerequisite is met */
+    pub fn construction_blocker(&self, building: &Building, culture: &str) -> Option<String> {
+        if self.has_building(building.get_id()) || self.construction.contains(building.get_id()) {
+            return Some(format!("{} already has {}", self.name, building.get_name()));
+        }
+        if !building.is_available_to(culture) {
+            return Some(format!("{} can not be built by the {} culture", building.get_name(), culture));
+        }
+        if self.level < building.get_settlement_level() {
+            return Some(format!("{} has to be level {} to build {}", self.name, building.get_settlement_level(), building.get_name()));
+        }
+        if let Some(b) = building.get_requires().iter().find(|b| !self.has_building(b)) {
+            return Some(format!("{} needs {} to build {}", self.name, b, building.get_name()));
+        }
+        None
+    }
+
+    /* Moves the construction queue on. A finished upgrade takes the place of the building it was
+    built from. */
+    pub fn advance_construction(&mut self, rules: &Rules) {
+        let building = match self.construction.advance().and_then(|b| rules.get_building(&b)) {
+            Some(b) => b,
+            None => return,
+        };
+
+        let buildings = self.buildings.get_or_insert_with(Vec::new);
+        buildings.retain(|b| rules.get_building(b).and_then(|b| b.get_upgrade()) != Some(building.get_id()));
+        buildings.push(building.get_id().to_string());
+        if let Some(level) = building.get_grants_level() {
+            self.level = self.level.max(level);
+        }
+        println!("{} has finished building {}", self.name, building.get_name());
+    }
+
+    /* Walls of all buildings add up */
+    pub fn fortification(&self, rules: &Rules) -> u32 {
+        self.buildings.iter().flatten().filter_map(|b| rules.get_building(b)).map(|b| b.get_fortification()).sum()
+    }
+
+    pub fn get_garrison(&self) -> &[Unit] {
+        &self.garrison
+    }
+
+    pub fn get_garrison_men(&self) -> u32 {
+        self.garrison.iter().map(|u| u.get_men()).sum()
+    }
+
+    pub fn garrison_power(&self, rules: &Rules) -> f32 {
+        self.garrison.iter().map(|u| u.power(rules.unit_type_of(u))).sum()
+    }
+
+    pub fn add_to_garrison(&mut self, unit: Unit) {
+        self.garrison.push(unit);
+    }
+
+    /* The garrison fights as an army of its own, give it back with return_garrison */
+    pub fn take_garrison(&mut self) -> Army {
+        let units = std::mem::take(&mut self.garrison);
+        Army::new(format!("{} Garrison", self.name), (0.0, 0.0), String::new(), units, Queue::new())
+    }
+
+    pub fn return_garrison(&mut self, army: Army) {
+        self.garrison = army.into_units();
+    }
+
+    /* A captured settlement loses its garrison, its governor and everything that was still being
+    built, and its people do not take kindly to their new ruler */
+    pub fn surrender(&mut self) {
+        self.garrison.clear();
+        self.governor = None;
+        self.recruitment = Queue::new();
+        self.construction = Queue::new();
+        self.conquest_turns = CONQUEST_TURNS;
+    }
+
+    /* Eats into the food stores of a besieged settlement and starves the garrison once they are empty */
+    pub fn besieged_turn(&mut self) {
+        if self.supplies > 0 {
+            self.supplies -= 1;
+            return;
+        }
+
+        for u in self.garrison.iter_mut() {
+            u.lose_share(STARVATION);
+            u.change_morale(STARVATION_MORALE);
+        }
+        self.garrison.retain(|u| u.get_men() > 0);
+        println!("The garrison of {} is starving", self.name);
+    }
+
+    /* Stores fill up again one turn at a time */
+    pub fn resupply(&mut self) {
+        self.supplies = (self.supplies + 1).min(BASE_SUPPLIES + self.level * SUPPLIES_PER_LEVEL);
+    }
+
+    /* The garrison is paid like any army */
+    pub fn upkeep(&self, rules: &Rules) -> Resources {
+        self.garrison.iter().map(|u| rules.unit_type_of(u).get_upkeep()).sum()
+    }
+
+    pub fn is_blockaded(&self) -> bool {
+        self.blockaded
+    }
+
+    pub fn set_blockaded(&mut self, blockaded: bool) {
+        self.blockaded = blockaded;
+    }
+
+    pub fn get_population(&self) -> u32 {
+        self.population
+    }
+
+    pub fn get_public_order(&self) -> i32 {
+        self.public_order
+    }
+
+    pub fn get_tax(&self) -> Tax {
+        self.tax
+    }
+
+    pub fn set_tax(&mut self, tax: Tax) {
+        self.tax = tax;
+    }
+
+    pub fn get_culture(&self) -> &str {
+        &self.culture
+    }
+
+    pub fn set_culture(&mut self, culture: String) {
+        self.culture = culture;
+    }
+
+    /* People who join a rebellion leave the settlement */
+    pub fn lose_population(&mut self, people: u32) {
+        self.population = self.population.saturating_sub(people);
+    }
+
+    pub fn max_population(&self, rules: &Rules) -> u32 {
+        let housing: u32 = self.buildings.iter().flatten().filter_map(|b| rules.get_building(b)).map(|b| b.get_housing()).sum();
+        self.level * POPULATION_PER_LEVEL + housing
+    }
+
+    /* Food grown here lets the population grow until the houses are full */
+    pub fn grow(&mut self, rules: &Rules, starving: bool) {
+        let rate = if starving { -FAMINE } else { BASE_GROWTH + self.production(rules).food.max(0) as f32 * FOOD_GROWTH };
+        let population = (self.population as f32 * (1.0 + rate)).round() as u32;
+        self.population = population.min(self.max_population(rules).max(self.population));
+    }
+
+    pub fn get_governor(&self) -> Option<&Character> {
+        self.governor.as_ref()
+    }
+
+    pub fn get_governor_mut(&mut self) -> &mut Option<Character> {
+        &mut self.governor
+    }
+
+    /* The value the public order is heading towards under the given ruler. The influence of the
+    governor and of the faction leader calm the people. */
+    pub fn target_public_order(&self, rules: &Rules, owner_culture: &str, leader_influence: u32) -> i32 {
+        let buildings: i32 = self.buildings.iter().flatten().filter_map(|b| rules.get_building(b)).map(|b| b.get_public_order()).sum();
+        let garrison = ((self.get_garrison_men() / MEN_PER_ORDER) as i32).min(MAX_GARRISON_ORDER);
+        let foreign = if !self.culture.is_empty() && !owner_culture.is_empty() && self.culture != owner_culture { FOREIGN_RULE_ORDER } else { 0 };
+        let conquest = self.conquest_turns as i32 * CONQUEST_ORDER;
+        let crowding = (self.population / 1000) as i32 * CROWDING_ORDER;
+        let influence = (self.governor.as_ref().map_or(0, |g| g.influence(rules)) + leader_influence) as i32 * character::INFLUENCE_ORDER;
+        (BASE_ORDER + self.tax.public_order() + buildings + garrison + foreign + conquest + crowding + influence).clamp(0, 100)
+    }
+
+    /* Public order changes slowly, memories of the conquest fade one turn at a time */
+    pub fn update_public_order(&mut self, rules: &Rules, owner_culture: &str, leader_influence: u32) {
+        let target = self.target_public_order(rules, owner_culture, leader_influence);
+        self.public_order += (target - self.public_order).clamp(-ORDER_DRIFT, ORDER_DRIFT);
+        self.conquest_turns = self.conquest_turns.saturating_sub(1);
+    }
+
+    pub fn is_rebellious(&self) -> bool {
+        self.public_order < REBELLION_ORDER
+    }
+
+    /* Trade buildings produce nothing while the port is blockaded. Taxes change the gold the
+    settlement pays, its people add to the manpower and a capable governor gets more gold and food
+    out of it. */
+    pub fn production(&self, rules: &Rules) -> Resources {
+        let buildings = self.buildings.iter().flatten().filter_map(|b| rules.get_building(b));
+        let mut production = BASE_PRODUCTION + buildings.filter(|b| !(self.blockaded && b.is_trade())).map(|b| b.get_production()).sum();
+        let management = 1.0 + self.governor.as_ref().map_or(0, |g| g.management(rules)) as f32 * character::MANAGEMENT_BONUS;
+        production.gold = (production.gold as f32 * self.tax.gold_factor() * management) as i32;
+        production.food = (production.food as f32 * management) as i32;
+        production.manpower += (self.population / PEOPLE_PER_MANPOWER) as i32;
+        production
+    }
+
+    /* What the settlement is worth to traders, its people and the gold its markets bring in */
+    pub fn trade_value(&self, rules: &Rules) -> i32 {
+        let markets: i32 = self.buildings.iter().flatten().filter_map(|b| rules.get_building(b)).filter(|b| b.is_trade()).map(|b| b.get_production().gold).sum();
+        (self.population / PEOPLE_PER_TRADE) as i32 + markets
     }
 }
@@ -1,10 +1,107 @@
 use serde::{Deserialize, Serialize};
 
+use crate::game::resources::Resources;
+
+/* Definition of a kind of building, loaded from data/buildings */
 #[derive(Serialize, Deserialize, Clone)]
-pub struct Building {}
+pub struct Building {
+    /* File stem of the building file, used by settlement files and construction orders */
+    #[serde(default)]
+    id: String,
+    name: String,
+    /* Added to the owner's treasury every turn */
+    production: Resources,
+    /* Paid in full when the building is queued */
+    cost: Resources,
+    /* Turns the building spends in the construction queue */
+    turns: u32,
+    /* Building ids the settlement needs before this one can be started */
+    #[serde(default)]
+    requires: Vec<String>,
+    /* Lowest settlement level the building can be put up in */
+    #[serde(default)]
+    settlement_level: u32,
+    /* Cultures allowed to build it, an empty list allows everyone */
+    #[serde(default)]
+    cultures: Vec<String>,
+    /* Building id this one is replaced by once the upgrade is finished */
+    #[serde(default)]
+    upgrade: Option<String>,
+    /* Finishing the building raises the settlement to this level */
+    #[serde(default)]
+    grants_level: Option<u32>,
+    /* Walls, every point makes the garrison stronger when the settlement is assaulted */
+    #[serde(default)]
+    fortification: u32,
+    /* Its production comes from merchants, which a blockade keeps away from ports */
+    #[serde(default)]
+    trade: bool,
+    /* People the settlement can hold on top of what its level allows */
+    #[serde(default)]
+    housing: u32,
+    /* Added to the public order of the settlement every turn */
+    #[serde(default)]
+    public_order: i32,
+}
 
 impl Building {
-    pub fn new() -> Self {
-        Building {}
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_production(&self) -> Resources {
+        self.production
+    }
+
+    pub fn get_cost(&self) -> Resources {
+        self.cost
+    }
+
+    pub fn get_turns(&self) -> u32 {
+        self.turns
+    }
+
+    pub fn get_requires(&self) -> &[String] {
+        &self.requires
+    }
+
+    pub fn get_settlement_level(&self) -> u32 {
+        self.settlement_level
+    }
+
+    pub fn get_upgrade(&self) -> Option<&str> {
+        self.upgrade.as_deref()
+    }
+
+    pub fn get_grants_level(&self) -> Option<u32> {
+        self.grants_level
+    }
+
+    pub fn get_fortification(&self) -> u32 {
+        self.fortification
+    }
+
+    pub fn is_trade(&self) -> bool {
+        self.trade
+    }
+
+    pub fn get_housing(&self) -> u32 {
+        self.housing
+    }
+
+    pub fn get_public_order(&self) -> i32 {
+        self.public_order
+    }
+
+    pub fn is_available_to(&self, culture: &str) -> bool {
+        self.cultures.is_empty() || self.cultures.iter().any(|c| c == culture)
     }
 }
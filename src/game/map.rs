@@ -1,20 +1,106 @@
-mod landscape;
-mod region;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+pub(crate) mod landscape;
+pub(crate) mod pathfinding;
+pub(crate) mod region;
+
+/* Movement points a fleet spends to sail into a port */
+const PORT_COST: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Map {
-    landscapes: Option<Vec<landscape::Landscape>>,
-    regions: Option<Vec<region::Region>>,
+    landscapes: Vec<landscape::Landscape>,
+    regions: Vec<region::Region>,
 }
 
 impl Map {
     pub fn new() -> Self {
         Map {
-            landscapes: None,
-            regions: None,
+            landscapes: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn get_regions(&self) -> &[region::Region] {
+        &self.regions
+    }
+
+    pub fn get_region(&self, id: &str) -> Option<&region::Region> {
+        self.regions.iter().find(|r| r.get_id() == id)
+    }
+
+    pub fn get_region_mut(&mut self, id: &str) -> Option<&mut region::Region> {
+        self.regions.iter_mut().find(|r| r.get_id() == id)
+    }
+
+    pub fn get_settlement_region(&self, settlement: &str) -> Option<&region::Region> {
+        self.regions.iter().find(|r| r.get_settlement() == Some(settlement))
+    }
+
+    pub fn get_landscape(&self, name: &str) -> Option<&landscape::Landscape> {
+        self.landscapes.iter().find(|l| l.get_name() == name)
+    }
+
+    /* Movement points needed to enter the region, None if its landscape is impassable */
+    pub fn entry_cost(&self, region: &region::Region) -> Option<u32> {
+        match self.get_landscape(region.get_landscape()) {
+            Some(l) if l.is_passable() => Some(l.get_movement_cost()),
+            _ => None,
         }
     }
 
+    pub fn is_naval(&self, region: &region::Region) -> bool {
+        self.get_landscape(region.get_landscape()).is_some_and(|l| l.is_naval())
+    }
+
+    /* A land region with a settlement on the shore of a sea region */
+    pub fn is_port(&self, region: &region::Region) -> bool {
+        region.get_settlement().is_some() && !self.is_naval(region) && self.get_sea_regions(region).next().is_some()
+    }
+
+    /* Sea regions bordering the region */
+    pub fn get_sea_regions<'a>(&'a self, region: &'a region::Region) -> impl Iterator<Item = &'a region::Region> {
+        region.get_sea_neighbours().iter().filter_map(move |n| self.get_region(n)).filter(move |r| self.is_naval(r))
+    }
+
+    /* Movement points a fleet needs to enter the region, None for land that is not a port */
+    pub fn sailing_cost(&self, region: &region::Region) -> Option<u32> {
+        match self.get_landscape(region.get_landscape()) {
+            Some(l) if l.is_naval() => Some(l.get_movement_cost()),
+            _ if self.is_port(region) => Some(PORT_COST),
+            _ => None,
+        }
+    }
+
+    /* Ids of all regions at most steps land or sea borders away, the region itself included */
+    pub fn regions_within(&self, region: &str, steps: u32) -> Vec<String> {
+        let mut found: Vec<String> = vec![region.to_string()];
+        let mut border: Vec<String> = found.clone();
+        for _ in 0..steps {
+            let mut next: Vec<String> = Vec::new();
+            for id in border.iter().filter_map(|b| self.get_region(b)) {
+                for n in id.get_land_neighbours().iter().chain(id.get_sea_neighbours()) {
+                    if !found.contains(n) {
+                        found.push(n.clone());
+                        next.push(n.clone());
+                    }
+                }
+            }
+            border = next;
+        }
+        found
+    }
+
+    /* Cheapest route between two regions, see pathfinding::find_path */
+    pub fn find_path<F: Fn(&region::Region) -> bool>(&self, from: &str, to: &str, may_cross: F) -> Option<pathfinding::Path> {
+        pathfinding::find_path(self, from, to, may_cross)
+    }
+
+    /* Cheapest route of a fleet between two regions, see pathfinding::find_sea_path */
+    pub fn find_sea_path<F: Fn(&region::Region) -> bool>(&self, from: &str, to: &str, may_cross: F) -> Option<pathfinding::Path> {
+        pathfinding::find_sea_path(self, from, to, may_cross)
+    }
+
     pub fn retreive_event_code(&mut self, code: &str) {
 
     }
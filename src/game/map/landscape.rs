@@ -1,8 +1,59 @@
-#[derive(Clone)]
-pub struct Landscape {}
+use serde::{Deserialize, Serialize};
+
+use crate::game::turn::Season;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Landscape {
+    name: String,
+    /* Movement points an army spends to enter a region of this landscape */
+    movement_cost: u32,
+    passable: bool,
+    /* Extra fighting power of the region owner, 0.25 means a quarter stronger */
+    defence_bonus: f32,
+    /* Armies standing on high ground see this many regions further */
+    #[serde(default)]
+    sight_bonus: u32,
+    /* Water that only fleets can sail, movement_cost is then what a fleet spends to enter */
+    #[serde(default)]
+    naval: bool,
+    /* Share of the men an army out of supply loses here every turn */
+    #[serde(default)]
+    attrition: f32,
+    /* Attrition doubles in this season, e.g. winter in the mountains */
+    #[serde(default)]
+    harsh_season: Option<Season>,
+}
 
 impl Landscape {
-    pub fn new() -> Self {
-        Landscape {}
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_movement_cost(&self) -> u32 {
+        self.movement_cost
+    }
+
+    pub fn is_passable(&self) -> bool {
+        self.passable
+    }
+
+    pub fn get_defence_bonus(&self) -> f32 {
+        self.defence_bonus
+    }
+
+    pub fn get_sight_bonus(&self) -> u32 {
+        self.sight_bonus
+    }
+
+    pub fn is_naval(&self) -> bool {
+        self.naval
+    }
+
+    pub fn get_attrition(&self, season: Season) -> f32 {
+        if self.harsh_season == Some(season) {
+            self.attrition * 2.0
+        } else {
+            self.attrition
+        }
     }
 }
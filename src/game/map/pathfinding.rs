@@ -0,0 +1,221 @@
+use std::{cmp::Ordering, collections::{BinaryHeap, HashMap}};
+
+use super::{region::Region, Map};
+
+/* Regions an army walks through, without the region it starts in */
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub regions: Vec<String>,
+    pub cost: u32,
+}
+
+struct Node {
+    estimate: f32,
+    cost: u32,
+    region: String,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    /* Reversed, so the BinaryHeap pops the cheapest estimate first */
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal)
+    }
+}
+
+/* Armies walk over land borders, fleets sail over sea borders */
+#[derive(Clone, Copy)]
+enum Medium {
+    Land,
+    Sea,
+}
+
+impl Medium {
+    fn neighbours(self, region: &Region) -> &[String] {
+        match self {
+            Medium::Land => region.get_land_neighbours(),
+            Medium::Sea => region.get_sea_neighbours(),
+        }
+    }
+
+    fn entry_cost(self, map: &Map, region: &Region) -> Option<u32> {
+        match self {
+            Medium::Land => map.entry_cost(region),
+            Medium::Sea => map.sailing_cost(region),
+        }
+    }
+}
+
+/* A* search over the land neighbours. may_cross decides if a region, the target included, can be
+entered at all. */
+pub fn find_path<F: Fn(&Region) -> bool>(map: &Map, from: &str, to: &str, may_cross: F) -> Option<Path> {
+    search(map, from, to, may_cross, Medium::Land)
+}
+
+/* The same search for fleets, over sea regions and ports */
+pub fn find_sea_path<F: Fn(&Region) -> bool>(map: &Map, from: &str, to: &str, may_cross: F) -> Option<Path> {
+    search(map, from, to, may_cross, Medium::Sea)
+}
+
+fn search<F: Fn(&Region) -> bool>(map: &Map, from: &str, to: &str, may_cross: F, medium: Medium) -> Option<Path> {
+    let target = map.get_region(to)?;
+    map.get_region(from)?;
+    medium.entry_cost(map, target)?;
+    if !may_cross(target) {
+        return None;
+    }
+    if from == to {
+        return Some(Path { regions: Vec::new(), cost: 0 });
+    }
+
+    let scale = heuristic_scale(map, medium);
+    let estimate = |r: &Region| distance(r, target) * scale;
+
+    let mut open: BinaryHeap<Node> = BinaryHeap::new();
+    let mut costs: HashMap<String, u32> = HashMap::new();
+    let mut previous: HashMap<String, String> = HashMap::new();
+
+    costs.insert(from.to_string(), 0);
+    open.push(Node { estimate: 0.0, cost: 0, region: from.to_string() });
+
+    while let Some(node) = open.pop() {
+        if node.region == to {
+            let mut regions = vec![node.region];
+            while let Some(p) = previous.get(regions.last().unwrap()) {
+                if p == from {
+                    break;
+                }
+                regions.push(p.clone());
+            }
+            regions.reverse();
+            return Some(Path { regions, cost: node.cost });
+        }
+
+        /* Stale entry, a cheaper way to this region was found after it was queued */
+        if node.cost > costs[&node.region] {
+            continue;
+        }
+
+        let region = map.get_region(&node.region).unwrap();
+        for neighbour_id in medium.neighbours(region) {
+            let neighbour = map.get_region(neighbour_id).unwrap();
+            if !may_cross(neighbour) {
+                continue;
+            }
+
+            let entry_cost = match medium.entry_cost(map, neighbour) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let cost = node.cost + entry_cost;
+            if costs.get(neighbour_id).is_some_and(|&c| c <= cost) {
+                continue;
+            }
+
+            costs.insert(neighbour_id.clone(), cost);
+            previous.insert(neighbour_id.clone(), node.region.clone());
+            open.push(Node { estimate: cost as f32 + estimate(neighbour), cost, region: neighbour_id.clone() });
+        }
+    }
+
+    None
+}
+
+fn distance(a: &Region, b: &Region) -> f32 {
+    let (ax, ay) = a.get_position();
+    let (bx, by) = b.get_position();
+    ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt()
+}
+
+/* Lowest movement cost per unit of map distance over all connections of the medium. Multiplying
+the straight distance with it never overestimates the remaining cost, which keeps A* exact. */
+fn heuristic_scale(map: &Map, medium: Medium) -> f32 {
+    let mut scale = f32::MAX;
+    for region in map.get_regions() {
+        for neighbour_id in medium.neighbours(region) {
+            let neighbour = map.get_region(neighbour_id).unwrap();
+            if let Some(cost) = medium.entry_cost(map, neighbour) {
+                let d = distance(region, neighbour);
+                if d > 0.0 {
+                    scale = scale.min(cost as f32 / d);
+                }
+            }
+        }
+    }
+
+    if scale == f32::MAX {
+        0.0
+    } else {
+        scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn region(id: &str, landscape: &str, position: (f32, f32), land: &[&str], sea: &[&str]) -> serde_json::Value {
+        json!({"id": id, "name": id, "owner": null, "landscape": landscape, "settlement": null, "position": [position.0, position.1], "land_neighbours": land, "sea_neighbours": sea})
+    }
+
+    /* The straight way from west to east leads over the mountains, around them through the
+    fields is longer on the map but cheaper to walk */
+    fn map() -> Map {
+        serde_json::from_value(json!({
+            "landscapes": [
+                {"name": "plains", "movement_cost": 2, "passable": true, "defence_bonus": 0.0},
+                {"name": "mountains", "movement_cost": 5, "passable": true, "defence_bonus": 0.0},
+                {"name": "sea", "movement_cost": 1, "passable": false, "defence_bonus": 0.0, "naval": true}
+            ],
+            "regions": [
+                region("west", "plains", (0.0, 0.0), &["pass", "fields"], &[]),
+                region("pass", "mountains", (1.0, 0.0), &["west", "east"], &[]),
+                region("fields", "plains", (1.0, 2.0), &["west", "east"], &[]),
+                region("east", "plains", (2.0, 0.0), &["pass", "fields"], &["bay"]),
+                region("bay", "sea", (3.0, 0.0), &[], &["east"]),
+                region("island", "plains", (5.0, 5.0), &[], &[])
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_the_cheapest_path() {
+        let map = map();
+        let path = find_path(&map, "west", "east", |_| true).unwrap();
+        assert_eq!(path.regions, vec!["fields", "east"]);
+        assert_eq!(path.cost, 4);
+
+        let path = find_path(&map, "west", "east", |r| r.get_id() != "fields").unwrap();
+        assert_eq!(path.regions, vec!["pass", "east"]);
+        assert_eq!(path.cost, 7);
+
+        let path = find_path(&map, "west", "west", |_| true).unwrap();
+        assert!(path.regions.is_empty());
+    }
+
+    #[test]
+    fn unreachable_targets_have_no_path() {
+        let map = map();
+        assert!(find_path(&map, "west", "island", |_| true).is_none());
+        assert!(find_path(&map, "west", "bay", |_| true).is_none());
+        assert!(find_path(&map, "west", "nowhere", |_| true).is_none());
+        assert!(find_path(&map, "west", "east", |r| r.get_id() == "east").is_none());
+    }
+}
@@ -1,8 +1,54 @@
-#[derive(Clone)]
-pub struct Region {}
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Region {
+    /* Stable identifier used by armies, saves and the neighbour lists */
+    id: String,
+    name: String,
+    /* File stem of the owning faction, None for unclaimed land */
+    owner: Option<String>,
+    landscape: String,
+    /* Name of the settlement in this region, if there is one */
+    settlement: Option<String>,
+    position: (f32, f32),
+    land_neighbours: Vec<String>,
+    sea_neighbours: Vec<String>,
+}
 
 impl Region {
-    pub fn new() -> Self {
-        Region {}
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn set_owner(&mut self, owner: Option<String>) {
+        self.owner = owner;
+    }
+
+    pub fn get_landscape(&self) -> &str {
+        &self.landscape
+    }
+
+    pub fn get_settlement(&self) -> Option<&str> {
+        self.settlement.as_deref()
+    }
+
+    pub fn get_position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    pub fn get_land_neighbours(&self) -> &[String] {
+        &self.land_neighbours
+    }
+
+    pub fn get_sea_neighbours(&self) -> &[String] {
+        &self.sea_neighbours
     }
 }
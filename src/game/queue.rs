@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use super::resources::Resources;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct QueueEntry {
+    /* The unit type or building being paid for */
+    id: String,
+    turns_left: u32,
+    /* What was paid, handed back if the entry is cancelled */
+    cost: Resources,
+}
+
+/* Units waiting to be raised or buildings waiting to be put up. Only the first entry makes
+progress, the others wait their turn. */
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Queue {
+    entries: Vec<QueueEntry>,
+}
+
+impl Queue {
+    pub fn new() -> Self {
+        Queue { entries: Vec::new() }
+    }
+
+    pub fn push(&mut self, id: String, turns: u32, cost: Resources) {
+        self.entries.push(QueueEntry {
+            id,
+            turns_left: turns,
+            cost,
+        });
+    }
+
+    pub fn get_ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.id.as_str())
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.iter().any(|e| e.id == id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /* Removes the entry and returns the refund */
+    pub fn cancel(&mut self, index: usize) -> Option<Resources> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        Some(self.entries.remove(index).cost)
+    }
+
+    /* Moves the queue one turn on and returns the id of a finished entry */
+    pub fn advance(&mut self) -> Option<String> {
+        let entry = self.entries.first_mut()?;
+        entry.turns_left = entry.turns_left.saturating_sub(1);
+        if entry.turns_left == 0 {
+            return Some(self.entries.remove(0).id);
+        }
+        None
+    }
+}
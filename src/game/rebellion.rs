@@ -0,0 +1,33 @@
+use super::faction::{army::unit::Unit, settlement::Settlement, REBELS};
+use super::rules::Rules;
+
+/* Every this many people raise one more unit of rebels, up to MAX_REBEL_UNITS */
+const PEOPLE_PER_UNIT: u32 = 1500;
+const MAX_REBEL_UNITS: u32 = 5;
+
+/* A settlement that rose against its ruler, for the turn report and to put the rebels on the map */
+pub struct Rising {
+    pub settlement: String,
+    pub owner: String,
+    pub region: String,
+    pub position: (f32, f32),
+    pub units: Vec<Unit>,
+}
+
+/* Arms the people of a rebellious settlement with the cheapest units of their culture. The men
+leave the settlement's population. None if the settlement is calm or its culture fields no units. */
+pub fn rise(settlement: &mut Settlement, rules: &Rules) -> Option<Vec<Unit>> {
+    if !settlement.is_rebellious() {
+        return None;
+    }
+
+    let unit_type = rules
+        .get_unit_types()
+        .iter()
+        .filter(|u| u.is_available_to(REBELS, settlement.get_culture()))
+        .min_by_key(|u| u.get_cost().gold)?;
+
+    let count = (1 + settlement.get_population() / PEOPLE_PER_UNIT).min(MAX_REBEL_UNITS);
+    settlement.lose_population(count * unit_type.get_men());
+    Some((0..count).map(|_| Unit::new(unit_type)).collect())
+}
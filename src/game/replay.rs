@@ -0,0 +1,124 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::Diplomacy;
+use super::faction::Faction;
+use super::map::Map;
+use super::siege::Siege;
+use super::turn::TurnManager;
+
+/* Offset basis and prime of the 64 bit FNV-1a hash. Unlike DefaultHasher it never changes between
+Rust versions, so hashes in old logs stay comparable. */
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/* An event code as a faction gave it */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Command {
+    pub turn: u32,
+    pub faction: String,
+    pub code: String,
+}
+
+/* Hash of the campaign state at the start of a turn */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct TurnHash {
+    pub turn: u32,
+    pub hash: u64,
+}
+
+/* Everything needed to play a campaign again from the starting world: the player faction, the
+seed and every command of the player and the AI in the order they were given */
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CommandLog {
+    player_faction: String,
+    seed: u64,
+    commands: Vec<Command>,
+    hashes: Vec<TurnHash>,
+}
+
+/* The parts of the campaign a turn changes, hashed to find where a replay diverges */
+#[derive(Serialize)]
+pub struct State<'a> {
+    pub factions: &'a [Faction],
+    pub player_faction: &'a Faction,
+    pub map: &'a Map,
+    pub diplomacy: &'a Diplomacy,
+    pub sieges: &'a [Siege],
+    pub turn_manager: &'a TurnManager,
+}
+
+impl State<'_> {
+    pub fn hash(&self) -> u64 {
+        let json = serde_json::to_vec(self).expect("Campaign state can always be written as JSON");
+        json.iter().fold(FNV_OFFSET, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+    }
+}
+
+impl CommandLog {
+    pub fn new(player_faction: &str, seed: u64) -> Self {
+        CommandLog {
+            player_faction: player_faction.to_string(),
+            seed,
+            commands: Vec::new(),
+            hashes: Vec::new(),
+        }
+    }
+
+    pub fn read(file: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Could not parse {}: {}", file, e))
+    }
+
+    /* Writes the log into the directory, named after the player faction and the seed. Returns the file. */
+    pub fn write(&self, directory: &str) -> Result<String, String> {
+        let file = format!("{}{}_{}.json", directory, self.player_faction, self.seed);
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::create_dir_all(directory).and_then(|_| fs::write(&file, content)).map_err(|e| format!("Could not write {}: {}", file, e))?;
+        Ok(file)
+    }
+
+    pub fn get_player_faction(&self) -> &str {
+        &self.player_faction
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /* Commands of the player only, the AI gives its commands again during a replay */
+    pub fn player_commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter().filter(move |c| c.faction == self.player_faction)
+    }
+
+    pub fn record(&mut self, turn: u32, faction: &str, code: &str) {
+        self.commands.push(Command { turn, faction: faction.to_string(), code: code.to_string() });
+    }
+
+    pub fn record_hash(&mut self, turn: u32, hash: u64) {
+        self.hashes.push(TurnHash { turn, hash });
+    }
+
+    pub fn get_hashes(&self) -> &[TurnHash] {
+        &self.hashes
+    }
+
+    /* First turn whose state differs between the logs, None if every turn both logs reached matches */
+    pub fn first_divergence(&self, other: &CommandLog) -> Option<u32> {
+        self.hashes.iter().zip(other.hashes.iter()).find(|(a, b)| a != b).map(|(a, _)| a.turn)
+    }
+
+    /* The first command of the turn that differs between the logs, either side may have run out */
+    pub fn first_difference(&self, other: &CommandLog, turn: u32) -> Option<(Option<Command>, Option<Command>)> {
+        let mut own = self.commands.iter().filter(|c| c.turn == turn);
+        let mut others = other.commands.iter().filter(|c| c.turn == turn);
+        loop {
+            match (own.next(), others.next()) {
+                (None, None) => return None,
+                (a, b) if a != b => return Some((a.cloned(), b.cloned())),
+                _ => {}
+            }
+        }
+    }
+}
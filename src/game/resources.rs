@@ -0,0 +1,67 @@
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/* Amounts of everything a faction produces and spends. Also used for stockpiles, costs and
+upkeep, so values can be negative. */
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+pub struct Resources {
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub food: i32,
+    #[serde(default)]
+    pub manpower: i32,
+}
+
+impl Resources {
+    pub fn new(gold: i32, food: i32, manpower: i32) -> Self {
+        Resources { gold, food, manpower }
+    }
+
+    pub fn can_afford(&self, cost: Resources) -> bool {
+        self.gold >= cost.gold && self.food >= cost.food && self.manpower >= cost.manpower
+    }
+}
+
+impl Add for Resources {
+    type Output = Resources;
+
+    fn add(self, other: Resources) -> Resources {
+        Resources::new(self.gold + other.gold, self.food + other.food, self.manpower + other.manpower)
+    }
+}
+
+impl AddAssign for Resources {
+    fn add_assign(&mut self, other: Resources) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Resources {
+    type Output = Resources;
+
+    fn sub(self, other: Resources) -> Resources {
+        Resources::new(self.gold - other.gold, self.food - other.food, self.manpower - other.manpower)
+    }
+}
+
+impl SubAssign for Resources {
+    fn sub_assign(&mut self, other: Resources) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i32> for Resources {
+    type Output = Resources;
+
+    fn mul(self, factor: i32) -> Resources {
+        Resources::new(self.gold * factor, self.food * factor, self.manpower * factor)
+    }
+}
+
+impl std::iter::Sum for Resources {
+    fn sum<I: Iterator<Item = Resources>>(iter: I) -> Resources {
+        iter.fold(Resources::default(), |a, b| a + b)
+    }
+}
@@ -0,0 +1,64 @@
+use super::event::Event;
+use super::faction::army::unit::{unit_type::UnitType, Unit};
+use super::faction::character::{character_trait::Trait, culture::Culture};
+use super::faction::settlement::building::Building;
+
+/* Static game data from the data directory that does not change during a campaign */
+#[derive(Clone)]
+pub struct Rules {
+    unit_types: Vec<UnitType>,
+    buildings: Vec<Building>,
+    traits: Vec<Trait>,
+    cultures: Vec<Culture>,
+    events: Vec<Event>,
+}
+
+impl Rules {
+    pub fn new(unit_types: Vec<UnitType>, buildings: Vec<Building>, traits: Vec<Trait>, cultures: Vec<Culture>, events: Vec<Event>) -> Self {
+        Rules { unit_types, buildings, traits, cultures, events }
+    }
+
+    pub fn get_unit_types(&self) -> &[UnitType] {
+        &self.unit_types
+    }
+
+    pub fn get_unit_type(&self, id: &str) -> Option<&UnitType> {
+        self.unit_types.iter().find(|u| u.get_id() == id)
+    }
+
+    /* Unit types of units are checked when the world or a save is loaded, so this can not fail later on */
+    pub fn unit_type_of(&self, unit: &Unit) -> &UnitType {
+        match self.get_unit_type(unit.get_unit_type()) {
+            Some(u) => u,
+            None => panic!("Unit of unknown type: {}", unit.get_unit_type()),
+        }
+    }
+
+    pub fn get_buildings(&self) -> &[Building] {
+        &self.buildings
+    }
+
+    pub fn get_building(&self, id: &str) -> Option<&Building> {
+        self.buildings.iter().find(|b| b.get_id() == id)
+    }
+
+    pub fn get_traits(&self) -> &[Trait] {
+        &self.traits
+    }
+
+    pub fn get_trait(&self, id: &str) -> Option<&Trait> {
+        self.traits.iter().find(|t| t.get_id() == id)
+    }
+
+    pub fn get_culture(&self, id: &str) -> Option<&Culture> {
+        self.cultures.iter().find(|c| c.get_id() == id)
+    }
+
+    pub fn get_events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn get_event(&self, id: &str) -> Option<&Event> {
+        self.events.iter().find(|e| e.get_id() == id)
+    }
+}
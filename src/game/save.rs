@@ -0,0 +1,332 @@
+use std::{error, fmt, fs, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::diplomacy::Diplomacy;
+use super::event::Happening;
+use super::faction::{character::Character, Faction};
+use super::map::Map;
+use super::replay::CommandLog;
+use super::rules::Rules;
+use super::siege::Siege;
+use super::turn::TurnManager;
+use super::victory::Outcome;
+use super::world::WorldError;
+
+pub(crate) mod migration;
+
+/* Written into every save. Raise it together with a new step in migration::STEPS whenever the
+saved structs change in a way serde defaults can not cover. */
+pub const SAVE_VERSION: u32 = 2;
+/* Used when config/game.json is missing */
+const AUTOSAVE_TURNS: u32 = 5;
+const AUTOSAVE_SLOTS: u32 = 3;
+
+/* Autosave part of config/game.json */
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct AutosaveSettings {
+    /* Turns between two autosaves, 0 turns autosaving off */
+    #[serde(default = "default_autosave_turns")]
+    autosave_turns: u32,
+    /* Autosaves go round these slots, the oldest one is replaced */
+    #[serde(default = "default_autosave_slots")]
+    autosave_slots: u32,
+}
+
+fn default_autosave_turns() -> u32 {
+    AUTOSAVE_TURNS
+}
+
+fn default_autosave_slots() -> u32 {
+    AUTOSAVE_SLOTS
+}
+
+impl AutosaveSettings {
+    pub fn load(file: &str) -> Self {
+        let settings = fs::read_to_string(file).map_err(|e| e.to_string()).and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()));
+        match settings {
+            Ok(s) => s,
+            Err(e) => {
+                println!("Could not read {}, autosaving every {} turns: {}", file, AUTOSAVE_TURNS, e);
+                AutosaveSettings { autosave_turns: AUTOSAVE_TURNS, autosave_slots: AUTOSAVE_SLOTS }
+            }
+        }
+    }
+
+    /* For campaigns that must not touch the save slots, like replays */
+    pub fn none() -> Self {
+        AutosaveSettings { autosave_turns: 0, autosave_slots: 0 }
+    }
+
+    /* Slot the autosave at the start of the turn goes into, None if there is none this turn */
+    pub fn slot(&self, turn: u32) -> Option<String> {
+        if self.autosave_turns == 0 || self.autosave_slots == 0 || !turn.is_multiple_of(self.autosave_turns) {
+            return None;
+        }
+        Some(format!("autosave_{}", (turn / self.autosave_turns) % self.autosave_slots))
+    }
+}
+
+/* Shown in the load screen without reading the whole campaign */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Metadata {
+    pub slot: String,
+    pub turn: u32,
+    pub faction: String,
+    /* Seconds since the unix epoch */
+    pub timestamp: u64,
+}
+
+/* Everything about a running campaign that does not come from the data files. The rules are
+loaded from the data path again when a campaign is restored. */
+#[derive(Serialize, Deserialize)]
+pub struct Campaign {
+    pub factions: Vec<Faction>,
+    pub player_faction: Faction,
+    pub map: Map,
+    pub diplomacy: Diplomacy,
+    pub sieges: Vec<Siege>,
+    pub turn_manager: TurnManager,
+    pub outcome: Option<Outcome>,
+    pub seed: u64,
+    pub report: Vec<String>,
+    pub happenings: Vec<Happening>,
+    pub fired_events: Vec<String>,
+    pub pending_events: Vec<String>,
+    /* Lets a loaded campaign still be replayed from the starting world */
+    #[serde(default)]
+    pub command_log: CommandLog,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Save {
+    /* Saves from before versioning have none, they count as version 0 */
+    version: u32,
+    pub metadata: Metadata,
+    pub campaign: Campaign,
+}
+
+/* Only the metadata of a save, for listing the slots */
+#[derive(Deserialize)]
+struct Header {
+    metadata: Metadata,
+}
+
+impl Save {
+    pub fn new(metadata: Metadata, campaign: Campaign) -> Self {
+        Save { version: SAVE_VERSION, metadata, campaign }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Read { file: String, error: String },
+    Write { file: String, error: String },
+    Parse { file: String, error: String },
+    InvalidSlot { slot: String },
+    TooNew { file: String, version: u32 },
+    Migration { file: String, version: u32, error: String },
+    World(WorldError),
+    /* The rules in the data path no longer have something the saved campaign uses */
+    MissingUnitType { slot: String, unit_type: String },
+    MissingBuilding { slot: String, building: String },
+    MissingTrait { slot: String, character: String, trait_id: String },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Read { file, error } => write!(f, "Could not read {}: {}", file, error),
+            SaveError::Write { file, error } => write!(f, "Could not write {}: {}", file, error),
+            SaveError::Parse { file, error } => write!(f, "Could not parse {}: {}", file, error),
+            SaveError::InvalidSlot { slot } => write!(f, "\"{}\" is not a valid save slot, only letters, digits, - and _ are allowed", slot),
+            SaveError::TooNew { file, version } => write!(f, "{} was saved by a newer version of the game (save version {}, this version reads up to {})", file, version, SAVE_VERSION),
+            SaveError::Migration { file, version, error } => write!(f, "Could not upgrade {} from save version {}: {}", file, version, error),
+            SaveError::World(e) => write!(f, "Could not load the rules of the saved campaign: {}", e),
+            SaveError::MissingUnitType { slot, unit_type } => write!(f, "The save {} uses the unit type \"{}\" which does not exist", slot, unit_type),
+            SaveError::MissingBuilding { slot, building } => write!(f, "The save {} uses the building \"{}\" which does not exist", slot, building),
+            SaveError::MissingTrait { slot, character, trait_id } => write!(f, "{} in the save {} has the trait \"{}\" which does not exist", character, slot, trait_id),
+        }
+    }
+}
+
+impl error::Error for SaveError {}
+
+impl Metadata {
+    pub fn new(slot: &str, turn: u32, faction: &str) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Metadata { slot: slot.to_string(), turn, faction: faction.to_string(), timestamp }
+    }
+}
+
+/* Slot names become file names, so they may not leave the save directory */
+fn slot_file(directory: &str, slot: &str) -> Result<String, SaveError> {
+    if slot.is_empty() || !slot.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(SaveError::InvalidSlot { slot: slot.to_string() });
+    }
+    Ok(format!("{}{}.json", directory, slot))
+}
+
+/* Writes the save into its slot, an older save in the slot is replaced */
+pub fn write(directory: &str, save: &Save) -> Result<(), SaveError> {
+    let file = slot_file(directory, &save.metadata.slot)?;
+    let content = match serde_json::to_string_pretty(save) {
+        Ok(c) => c,
+        Err(e) => return Err(SaveError::Write { file, error: e.to_string() }),
+    };
+
+    if let Err(e) = fs::create_dir_all(directory).and_then(|_| fs::write(&file, content)) {
+        return Err(SaveError::Write { file, error: e.to_string() });
+    }
+    Ok(())
+}
+
+pub fn read(directory: &str, slot: &str) -> Result<Save, SaveError> {
+    let file = slot_file(directory, slot)?;
+    let content = match fs::read_to_string(&file) {
+        Ok(c) => c,
+        Err(e) => return Err(SaveError::Read { file, error: e.to_string() }),
+    };
+
+    let mut value: Value = match serde_json::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => return Err(SaveError::Parse { file, error: e.to_string() }),
+    };
+
+    /* Older saves are upgraded step by step before they are read into the current structs */
+    let version = migration::version_of(&value);
+    if version > SAVE_VERSION {
+        return Err(SaveError::TooNew { file, version });
+    }
+    if let Err((version, error)) = migration::migrate(&mut value, version) {
+        return Err(SaveError::Migration { file, version, error });
+    }
+
+    match serde_json::from_value(value) {
+        Ok(s) => Ok(s),
+        Err(e) => Err(SaveError::Parse { file, error: e.to_string() }),
+    }
+}
+
+/* Saves only hold the ids of unit types, buildings and traits, the rules are loaded from the data
+path again. Every id the campaign uses has to be in them. */
+pub fn check(save: &Save, rules: &Rules) -> Result<(), SaveError> {
+    let slot = || save.metadata.slot.clone();
+    let campaign = &save.campaign;
+
+    for faction in campaign.factions.iter().chain(std::iter::once(&campaign.player_faction)) {
+        let armies = faction.get_armies().iter().chain(faction.get_fleets().iter().flat_map(|f| f.get_armies()));
+        let mut unit_types: Vec<&str> = Vec::new();
+        let mut characters: Vec<&Character> = faction.get_leader().into_iter().chain(faction.get_heir()).collect();
+        for a in armies {
+            unit_types.extend(a.get_units().iter().map(|u| u.get_unit_type()));
+            unit_types.extend(a.get_recruitment().get_ids());
+            characters.extend(a.get_general());
+        }
+
+        for s in faction.get_settlements() {
+            unit_types.extend(s.get_garrison().iter().map(|u| u.get_unit_type()));
+            unit_types.extend(s.get_recruitment().get_ids());
+            characters.extend(s.get_governor());
+            let mut buildings = s.get_buildings().iter().map(|b| b.as_str()).chain(s.get_construction().get_ids());
+            if let Some(b) = buildings.find(|b| rules.get_building(b).is_none()) {
+                return Err(SaveError::MissingBuilding { slot: slot(), building: b.to_string() });
+            }
+        }
+
+        if let Some(u) = unit_types.into_iter().find(|u| rules.get_unit_type(u).is_none()) {
+            return Err(SaveError::MissingUnitType { slot: slot(), unit_type: u.to_string() });
+        }
+        for c in characters {
+            if let Some(t) = c.get_traits().iter().find(|t| rules.get_trait(t).is_none()) {
+                return Err(SaveError::MissingTrait { slot: slot(), character: c.get_name().to_string(), trait_id: t.clone() });
+            }
+        }
+    }
+    Ok(())
+}
+
+/* Metadata of every save in the directory, newest first. Files that are no saves are left out. */
+pub fn list(directory: &str) -> Vec<Metadata> {
+    let entries = match fs::read_dir(directory) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut saves: Vec<Metadata> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|p| fs::read_to_string(&p).ok().map(|c| (p, c)))
+        .filter_map(|(p, c)| match serde_json::from_str::<Header>(&c) {
+            Ok(h) => Some(h.metadata),
+            Err(e) => {
+                println!("Skipping {}: {}", p.display(), e);
+                None
+            }
+        })
+        .collect();
+    saves.sort_by_key(|s| std::cmp::Reverse((s.timestamp, s.turn)));
+    saves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::World;
+    use crate::resource_manager::ResourceManager;
+
+    fn resource_manager() -> ResourceManager {
+        let mut resource_manager = ResourceManager::new("assets/", "config/", "data/");
+        resource_manager.set_world("assets/world/");
+        resource_manager
+    }
+
+    fn campaign(world: World) -> Campaign {
+        Campaign {
+            factions: world.factions,
+            player_faction: world.player_faction,
+            map: world.map,
+            diplomacy: world.diplomacy,
+            sieges: Vec::new(),
+            turn_manager: TurnManager::new(),
+            outcome: None,
+            seed: 7,
+            report: vec!["Turn report".to_string()],
+            happenings: Vec::new(),
+            fired_events: Vec::new(),
+            pending_events: Vec::new(),
+            command_log: CommandLog::new("arthedain", 7),
+        }
+    }
+
+    #[test]
+    fn loaded_save_matches_the_saved_campaign() {
+        let resource_manager = resource_manager();
+        let world = World::load(&resource_manager, "arthedain").unwrap();
+        let rules = World::load_rules(&resource_manager).unwrap();
+        let save = Save::new(Metadata::new("roundtrip", 1, "arthedain"), campaign(world));
+
+        let directory = format!("{}/barbaria_saves_{}/", std::env::temp_dir().display(), std::process::id());
+        write(&directory, &save).unwrap();
+        let loaded = read(&directory, "roundtrip");
+        fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(serde_json::to_value(&loaded.metadata).unwrap(), serde_json::to_value(&save.metadata).unwrap());
+        assert_eq!(serde_json::to_value(&loaded.campaign).unwrap(), serde_json::to_value(&save.campaign).unwrap());
+
+        /* The ids are checked against the rules of the data path */
+        assert!(check(&loaded, &rules).is_ok());
+        let no_rules = Rules::new(Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        assert!(matches!(check(&loaded, &no_rules), Err(SaveError::MissingUnitType { .. } | SaveError::MissingBuilding { .. })));
+    }
+
+    #[test]
+    fn slots_may_not_leave_the_save_directory() {
+        assert!(matches!(read("saves/", "../game"), Err(SaveError::InvalidSlot { .. })));
+    }
+}
@@ -0,0 +1,123 @@
+use serde_json::Value;
+
+use super::SAVE_VERSION;
+
+/* A step upgrades the JSON of a save by one version, STEPS[n] turns version n into version n + 1 */
+type Step = fn(&mut Value) -> Result<(), String>;
+const STEPS: &[Step] = &[v0_to_v1, v1_to_v2];
+
+/* Saves without a version number are from before versioning */
+pub fn version_of(save: &Value) -> u32 {
+    save.get("version").and_then(|v| v.as_u64()).map_or(0, |v| v as u32)
+}
+
+/* Runs every step from the save's version up to SAVE_VERSION. Returns the version that could not
+be upgraded along with the reason. */
+pub fn migrate(save: &mut Value, from: u32) -> Result<(), (u32, String)> {
+    for version in from..SAVE_VERSION {
+        let step = match STEPS.get(version as usize) {
+            Some(s) => s,
+            None => return Err((version, "there is no migration step for this version".to_string())),
+        };
+        step(save).map_err(|e| (version, e))?;
+
+        match save.as_object_mut() {
+            Some(s) => s.insert("version".to_string(), Value::from(version + 1)),
+            None => return Err((version, "the save is not a JSON object".to_string())),
+        };
+    }
+    Ok(())
+}
+
+/* The first saves only lacked the version number */
+fn v0_to_v1(save: &mut Value) -> Result<(), String> {
+    match save.get("campaign") {
+        Some(c) if c.is_object() => Ok(()),
+        _ => Err("the save has no campaign".to_string()),
+    }
+}
+
+/* Recruitment and construction queues share one entry type, which calls the unit type or
+building it is paying for its id */
+fn v1_to_v2(save: &mut Value) -> Result<(), String> {
+    match save.get_mut("campaign") {
+        Some(c) => {
+            rename_queue_ids(c);
+            Ok(())
+        }
+        None => Err("the save has no campaign".to_string()),
+    }
+}
+
+/* Queue entries are the only objects with turns_left next to a cost */
+fn rename_queue_ids(value: &mut Value) {
+    match value {
+        Value::Object(o) => {
+            if o.contains_key("turns_left") && o.contains_key("cost") {
+                for old in ["unit_type", "building"] {
+                    if let Some(id) = o.remove(old) {
+                        o.insert("id".to_string(), id);
+                    }
+                }
+            }
+            o.values_mut().for_each(rename_queue_ids);
+        }
+        Value::Array(a) => a.iter_mut().for_each(rename_queue_ids),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::game::save::{self, SaveError};
+
+    /* A save from before versioning with a recruitment and a construction queue */
+    fn old_save() -> Value {
+        json!({
+            "metadata": {"slot": "old", "turn": 3, "faction": "arthedain", "timestamp": 0},
+            "campaign": {
+                "armies": [{"name": "Host", "unit_type": "spearmen", "recruitment": {"entries": [{"unit_type": "spearmen", "turns_left": 1, "cost": {"gold": 80, "food": 0, "manpower": 25}}]}}],
+                "settlements": [{"name": "Fornost", "construction": {"entries": [{"building": "walls", "turns_left": 2, "cost": {"gold": 200, "food": 0, "manpower": 0}}]}}]
+            }
+        })
+    }
+
+    #[test]
+    fn old_saves_are_upgraded_to_the_current_version() {
+        let mut save = old_save();
+        assert_eq!(version_of(&save), 0);
+        migrate(&mut save, 0).unwrap();
+        assert_eq!(version_of(&save), SAVE_VERSION);
+
+        let campaign = &save["campaign"];
+        assert_eq!(campaign["armies"][0]["recruitment"]["entries"][0], json!({"id": "spearmen", "turns_left": 1, "cost": {"gold": 80, "food": 0, "manpower": 25}}));
+        assert_eq!(campaign["settlements"][0]["construction"]["entries"][0]["id"], "walls");
+        assert!(campaign["settlements"][0]["construction"]["entries"][0].get("building").is_none());
+        /* Only queue entries are renamed */
+        assert_eq!(campaign["armies"][0]["unit_type"], "spearmen");
+    }
+
+    #[test]
+    fn saves_without_a_campaign_can_not_be_upgraded() {
+        let mut save = json!({"metadata": {}});
+        assert_eq!(migrate(&mut save, 0).unwrap_err().0, 0);
+    }
+
+    #[test]
+    fn newer_saves_are_rejected() {
+        let mut save = old_save();
+        save["version"] = json!(SAVE_VERSION + 1);
+        let directory = format!("{}/barbaria_migration_{}/", std::env::temp_dir().display(), std::process::id());
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(format!("{}new.json", directory), save.to_string()).unwrap();
+
+        let result = save::read(&directory, "new");
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(matches!(result, Err(SaveError::TooNew { version, .. }) if version == SAVE_VERSION + 1));
+    }
+}
@@ -14,11 +14,15 @@ pub struct Screen {
     event_text_elements: Vec<TextElement>,
     event_texture_elements: Vec<TextureElement>,
     gui: Option<gui::Gui>,
+    /* Indices of the event texture elements rendered since the screen was opened, they can be clicked */
+    #[serde(skip)]
+    rendered_event_textures: Vec<usize>,
 }
 
 impl Screen {
     pub fn open(&mut self, engine: &mut engine::Engine, paths: Config) {
         let mut gui = gui::Gui::new(self.scale, self.position);
+        self.rendered_event_textures.clear();
         
         if self.background != "" {
             gui.add_background(engine, &self.background[..], (0.0, 0.0),(1000.0, 1000.0));
@@ -58,23 +62,57 @@ impl Screen {
     pub fn render_event_texture(&mut self, engine: &mut engine::Engine, element_name: &str) {
         let index: usize = element_name.parse().expect("Not a valid index");
         let texture_element = self.event_texture_elements.get(index).unwrap();
-        self.gui.as_mut().unwrap().add_element(engine, &texture_element.name, texture_element.position, texture_element.size)
+        self.gui.as_mut().unwrap().add_element(engine, &texture_element.name, texture_element.position, texture_element.size);
+        self.rendered_event_textures.push(index);
     }
 
-    pub fn render_event_text(&mut self, engine: &mut engine::Engine, element_name: &str, paths: &Config) {
+    /* Text is the stem of the text file to show instead of the one the element names */
+    pub fn render_event_text(&mut self, engine: &mut engine::Engine, element_name: &str, text: Option<&str>, paths: &Config) {
         let index: usize = element_name.parse().expect("Not a valid index");
         let text_element = self.event_text_elements.get(index).unwrap();
+        let text = text.unwrap_or(&text_element.text);
         for i in paths.resource_manager.get_assets("texts") {
             let name = i.split('/').last().unwrap().to_string();
             let name = name.split('.').next().unwrap();
-            if name == text_element.text {
+            if name == text {
                 let file_c = std::fs::read_to_string(i).expect("Could not read text file.");
-                self.gui.as_mut().unwrap().add_text(engine, text_element.position, text_element.fontsize, &text_element.font[..], &file_c, text_element.color);
+                self.render_event_string(engine, element_name, &file_c);
                 break;
             }
         }
     }
 
+    /* Shows the content as it is instead of a text file, e.g. the names of the save slots */
+    pub fn render_event_string(&mut self, engine: &mut engine::Engine, element_name: &str, content: &str) {
+        let index: usize = element_name.parse().expect("Not a valid index");
+        let text_element = self.event_text_elements.get(index).unwrap();
+        self.gui.as_mut().unwrap().add_text(engine, text_element.position, text_element.fontsize, &text_element.font[..], content, text_element.color);
+    }
+
+    /* Render units of the background and of every texture element */
+    pub fn get_render_units(&self) -> Vec<&str> {
+        let elements = self.texture_elements.iter().chain(self.event_texture_elements.iter()).map(|e| &e.name);
+        std::iter::once(&self.background).chain(elements).filter(|n| !n.is_empty()).map(|n| n.as_str()).collect()
+    }
+
+    /* Text file stems of every text element, empty ones are filled in while the screen is open */
+    pub fn get_texts(&self) -> Vec<&str> {
+        self.text_elements.iter().chain(self.event_text_elements.iter()).map(|e| e.text.as_str()).filter(|t| !t.is_empty()).collect()
+    }
+
+    pub fn get_fonts(&self) -> Vec<&str> {
+        self.text_elements.iter().chain(self.event_text_elements.iter()).map(|e| e.font.as_str()).collect()
+    }
+
+    pub fn get_event_codes(&self) -> Vec<&str> {
+        self.texture_elements.iter().chain(self.event_texture_elements.iter()).flat_map(|e| e.event_codes.iter()).map(|c| c.as_str()).filter(|c| !c.is_empty()).collect()
+    }
+
+    /* Number of event text and event texture elements, "render text <i>" and "render texture <i>" index them */
+    pub fn get_event_element_counts(&self) -> (usize, usize) {
+        (self.event_text_elements.len(), self.event_texture_elements.len())
+    }
+
     pub fn mouse_clicked(&self, listener: &mut Listener, cursor_pos: (f64, f64), screen_size: (f32, f32)) {
         /* y-Coordinates are upside down */
         let cursor_pos = (cursor_pos.0, screen_size.1 as f64 - cursor_pos.1);
@@ -88,7 +126,8 @@ impl Screen {
         /* Check if mouse is in range of GUI */
         if cursor_pos.0 >= self.position.0 && cursor_pos.0 <= self.position.0 + self.scale.0 && cursor_pos.1 >= self.position.1 && cursor_pos.1 <= self.position.1 + self.scale.1 {
             
-            for element in (&self.texture_elements).iter().rev() {
+            let rendered = self.rendered_event_textures.iter().filter_map(|i| self.event_texture_elements.get(*i));
+            for element in self.texture_elements.iter().chain(rendered).rev() {
                 let element_x = element.position.0 * (self.scale.0 / 1000.0) + self.position.0 - (element.size.0 / 2.0) * (self.scale.0 / 1000.0);
                 let element_x_end = element_x + (element.size.0 * (self.scale.0 / 1000.0));
                 let element_y = element.position.1 * (self.scale.1 / 1000.0) + self.position.1 ;
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/* Extra power of the garrison for every point of fortification the siege equipment has not yet overcome */
+const WALL_BONUS: f32 = 0.5;
+/* Turns the besiegers need to build enough equipment to overcome one point of fortification */
+const EQUIPMENT_TURNS: u32 = 2;
+
+/* What the two sides of a siege do in the next battle phase */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Wait,
+    /* The besiegers storm the walls */
+    Assault,
+    /* The garrison attacks the besiegers outside the walls */
+    Sally,
+}
+
+/* A settlement surrounded by the armies of a faction at war with its owner */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Siege {
+    region: String,
+    besieger: String,
+    defender: String,
+    turns: u32,
+    action: Action,
+    /* Strength of the garrison and its walls as of the last battle phase, for the AI to judge */
+    garrison_power: f32,
+    wall_bonus: f32,
+}
+
+impl Siege {
+    pub fn new(region: String, besieger: String, defender: String) -> Self {
+        Siege {
+            region,
+            besieger,
+            defender,
+            turns: 0,
+            action: Action::Wait,
+            garrison_power: 0.0,
+            wall_bonus: 0.0,
+        }
+    }
+
+    pub fn get_region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn get_besieger(&self) -> &str {
+        &self.besieger
+    }
+
+    pub fn get_defender(&self) -> &str {
+        &self.defender
+    }
+
+    pub fn get_action(&self) -> Action {
+        self.action
+    }
+
+    pub fn get_garrison_power(&self) -> f32 {
+        self.garrison_power
+    }
+
+    pub fn get_wall_bonus(&self) -> f32 {
+        self.wall_bonus
+    }
+
+    /* Works out how much the walls still help against the equipment built so far */
+    pub fn update_garrison(&mut self, garrison_power: f32, fortification: u32) {
+        self.garrison_power = garrison_power;
+        self.wall_bonus = fortification.saturating_sub(self.turns / EQUIPMENT_TURNS) as f32 * WALL_BONUS;
+    }
+
+    /* Another turn of building equipment and eating supplies */
+    pub fn advance(&mut self) {
+        self.turns += 1;
+        self.action = Action::Wait;
+    }
+
+    /* Siege orders look like "assault <region id>" for the besieger and "sally <region id>" for
+    the defender */
+    pub fn retreive_event_code(&mut self, code: &str, faction: &str) {
+        let mut split_string = code.split(' ');
+        match (split_string.next(), split_string.next()) {
+            (Some("assault"), Some(region)) if region == self.region && faction == self.besieger => self.action = Action::Assault,
+            (Some("sally"), Some(region)) if region == self.region && faction == self.defender => self.action = Action::Sally,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::diplomacy::Diplomacy;
+use super::map::Map;
+use super::turn::Season;
+
+/* Land borders an army can be away from a friendly settlement and still be fed from it */
+const SUPPLY_RANGE: u32 = 2;
+/* The search for a friendly settlement gives up here, armies further away are cut off */
+const MAX_DISTANCE: u32 = 6;
+/* Share of the men an army loses for every border beyond the supply range */
+const ATTRITION_PER_STEP: f32 = 0.02;
+
+/* Men an army lost to hunger and the land in one turn, for the turn report */
+pub struct Loss {
+    pub army: String,
+    pub region: String,
+    pub men: u32,
+    /* The army lost its last men and is gone */
+    pub perished: bool,
+}
+
+/* Land borders between the region and the closest settlement of the faction or of a faction that
+grants it military access, None if there is none within MAX_DISTANCE */
+pub fn distance_to_supply(map: &Map, diplomacy: &Diplomacy, faction: &str, region: &str) -> Option<u32> {
+    let mut found: Vec<String> = vec![region.to_string()];
+    let mut border: Vec<String> = found.clone();
+    for distance in 0..=MAX_DISTANCE {
+        for id in border.iter() {
+            let r = map.get_region(id)?;
+            if r.get_settlement().is_some() && r.get_owner().is_some_and(|o| diplomacy.has_military_access(faction, o)) {
+                return Some(distance);
+            }
+        }
+
+        let mut next: Vec<String> = Vec::new();
+        for r in border.iter().filter_map(|b| map.get_region(b)) {
+            for n in r.get_land_neighbours() {
+                if !found.contains(n) && map.get_region(n).and_then(|n| map.entry_cost(n)).is_some() {
+                    found.push(n.clone());
+                    next.push(n.clone());
+                }
+            }
+        }
+        border = next;
+    }
+    None
+}
+
+pub fn is_supplied(map: &Map, diplomacy: &Diplomacy, faction: &str, region: &str) -> bool {
+    distance_to_supply(map, diplomacy, faction, region).is_some_and(|d| d <= SUPPLY_RANGE)
+}
+
+/* Share of its men an army in the region loses this turn. Supplied armies are fed well enough to
+bear any land, the others suffer from the land, the season and the length of their supply line. */
+pub fn attrition(map: &Map, diplomacy: &Diplomacy, faction: &str, region: &str, season: Season) -> f32 {
+    let terrain = map.get_region(region).and_then(|r| map.get_landscape(r.get_landscape())).map_or(0.0, |l| l.get_attrition(season));
+    let distance = distance_to_supply(map, diplomacy, faction, region).unwrap_or(MAX_DISTANCE + 1);
+    if distance <= SUPPLY_RANGE {
+        0.0
+    } else {
+        (terrain + (distance - SUPPLY_RANGE) as f32 * ATTRITION_PER_STEP).min(1.0)
+    }
+}
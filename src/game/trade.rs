@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::Diplomacy;
+use super::faction::Faction;
+use super::map::Map;
+use super::rules::Rules;
+
+/* Share of the trade value of both settlements a route brings in as gold */
+const TRADE_SHARE: f32 = 0.25;
+/* Settlements further apart than this many regions do not trade */
+const MAX_ROUTE_LENGTH: usize = 8;
+
+/* A route from one of the faction's settlements to the nearest settlement of a trade partner */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TradeRoute {
+    pub partner: String,
+    /* Settlement names at both ends */
+    pub from: String,
+    pub to: String,
+    /* Region ids the caravans pass, both ends included */
+    pub regions: Vec<String>,
+    /* Gold the route brings every turn while it is open */
+    pub income: i32,
+    /* Faction whose army sits on the route, it brings nothing while the army is there */
+    pub disrupted_by: Option<String>,
+}
+
+impl TradeRoute {
+    pub fn is_disrupted(&self) -> bool {
+        self.disrupted_by.is_some()
+    }
+}
+
+/* Routes of the faction to all its trade partners over land. armies holds the faction id and region
+id of every army on the map. */
+pub fn find_routes(faction: &Faction, factions: &[&Faction], map: &Map, rules: &Rules, diplomacy: &Diplomacy, armies: &[(String, String)]) -> Vec<TradeRoute> {
+    let mut routes: Vec<TradeRoute> = Vec::new();
+
+    for partner in diplomacy.get_trade_partners(faction.get_id()) {
+        let partner = match factions.iter().find(|f| f.get_id() == partner) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        for settlement in faction.get_settlements() {
+            let start = match map.get_settlement_region(settlement.get_name()) {
+                Some(r) => r.get_id(),
+                None => continue,
+            };
+
+            /* Every settlement trades with the nearest settlement of the partner it can reach */
+            let nearest = partner
+                .get_settlements()
+                .iter()
+                .filter_map(|s| map.get_settlement_region(s.get_name()).map(|r| (s, r.get_id())))
+                .filter_map(|(s, r)| map.find_path(start, r, |_| true).map(|p| (s, p)))
+                .filter(|(_, p)| p.regions.len() <= MAX_ROUTE_LENGTH)
+                .min_by_key(|(_, p)| p.cost);
+            let (other, path) = match nearest {
+                Some(n) => n,
+                None => continue,
+            };
+
+            let regions: Vec<String> = std::iter::once(start.to_string()).chain(path.regions).collect();
+            let disrupted_by = armies
+                .iter()
+                .find(|(owner, region)| regions.contains(region) && (diplomacy.at_war(owner, faction.get_id()) || diplomacy.at_war(owner, partner.get_id())))
+                .map(|(owner, _)| owner.clone());
+            let income = ((settlement.trade_value(rules) + other.trade_value(rules)) as f32 * TRADE_SHARE) as i32;
+
+            routes.push(TradeRoute {
+                partner: partner.get_id().to_string(),
+                from: settlement.get_name().to_string(),
+                to: other.get_name().to_string(),
+                regions,
+                income,
+                disrupted_by,
+            });
+        }
+    }
+    routes
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/* Phases of a campaign turn in the order they are run */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Income,
+    PlayerOrders,
+    AiFactions,
+    Movement,
+    Battles,
+    EndOfTurn,
+}
+
+impl Phase {
+    pub fn next(self) -> Phase {
+        match self {
+            Phase::Income => Phase::PlayerOrders,
+            Phase::PlayerOrders => Phase::AiFactions,
+            Phase::AiFactions => Phase::Movement,
+            Phase::Movement => Phase::Battles,
+            Phase::Battles => Phase::EndOfTurn,
+            Phase::EndOfTurn => Phase::Income,
+        }
+    }
+}
+
+/* Every turn is one season, the year starts in spring */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    fn of_turn(turn: u32) -> Season {
+        match turn.saturating_sub(1) % 4 {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TurnManager {
+    turn: u32,
+    phase: Phase,
+}
+
+impl TurnManager {
+    pub fn new() -> Self {
+        TurnManager {
+            turn: 1,
+            phase: Phase::Income,
+        }
+    }
+
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn get_season(&self) -> Season {
+        Season::of_turn(self.turn)
+    }
+
+    pub fn get_phase(&self) -> Phase {
+        self.phase
+    }
+
+    /* Moves on to the next phase. Leaving the end of turn bookkeeping starts a new turn. */
+    pub fn next_phase(&mut self) -> Phase {
+        if self.phase == Phase::EndOfTurn {
+            self.turn += 1;
+        }
+        self.phase = self.phase.next();
+        self.phase
+    }
+}
@@ -0,0 +1,345 @@
+use std::{collections::BTreeMap, error, fmt, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::resource_manager::ResourceManager;
+
+use super::screen::Screen;
+use super::victory::Outcome;
+use super::world::{self, World, WorldError};
+
+/* Asset directories, the engine and the screens look their files up by the file stem */
+const ASSET_KINDS: &[&str] = &["animations", "cameras", "models", "projections", "shaders", "textures", "fonts", "render_units", "screens", "texts"];
+/* Directories of the data path World::load reads */
+const DATA_KINDS: &[&str] = &["units", "buildings", "traits", "cultures", "events"];
+/* Screens the game opens by their name instead of through an event code */
+const REQUIRED_SCREENS: &[&str] = &["main_menu", "campaign", "event"];
+
+/* Layout of the files in assets/render_units, every entry is the stem of an asset */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenderUnitFile {
+    animation: String,
+    camera: String,
+    model: String,
+    projection: String,
+    shader: String,
+    texture: String,
+}
+
+#[derive(Debug)]
+pub enum Problem {
+    World(WorldError),
+    MissingDirectory { directory: String },
+    Read { file: String, error: String },
+    Parse { file: String, error: String },
+    /* A name in the file that no file in the directory has as its stem */
+    MissingAsset { file: String, directory: String, name: String },
+    /* "render text <i>" or "render texture <i>" without an event element at the index */
+    MissingElement { file: String, code: String },
+    /* Used by the game itself, not by any file */
+    MissingRequired { directory: String, name: String },
+    DuplicateStem { directory: String, stem: String, files: Vec<String> },
+    DuplicateScreen { screen: String, files: Vec<String> },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::World(e) => write!(f, "{}", e),
+            Problem::MissingDirectory { directory } => write!(f, "The directory {} does not exist", directory),
+            Problem::Read { file, error } => write!(f, "Could not read {}: {}", file, error),
+            Problem::Parse { file, error } => write!(f, "Could not parse {}: {}", file, error),
+            Problem::MissingAsset { file, directory, name } => write!(f, "{} references \"{}\" which is not in {}", file, name, directory),
+            Problem::MissingElement { file, code } => write!(f, "{} uses the event code \"{}\" but has no event element with that index", file, code),
+            Problem::MissingRequired { directory, name } => write!(f, "The game needs \"{}\" which is not in {}", name, directory),
+            Problem::DuplicateStem { directory, stem, files } => write!(f, "More than one file in {} has the stem \"{}\", only one is used: {}", directory, stem, files.join(", ")),
+            Problem::DuplicateScreen { screen, files } => write!(f, "More than one screen is called \"{}\", only one is opened: {}", screen, files.join(", ")),
+        }
+    }
+}
+
+impl error::Error for Problem {}
+
+/* Files of a directory by their stem */
+type Stems = BTreeMap<String, Vec<String>>;
+
+/* Checks the screens, render units, texts and the world against each other. Unlike loading the
+game it goes on after a problem, so every problem is reported at once. */
+pub fn run(resource_manager: &ResourceManager) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = Vec::new();
+
+    let mut assets: BTreeMap<&str, Stems> = BTreeMap::new();
+    for kind in ASSET_KINDS {
+        let directory = resource_manager.get_asset_directory(kind);
+        if !Path::new(&directory).is_dir() {
+            problems.push(Problem::MissingDirectory { directory });
+            assets.insert(kind, Stems::new());
+            continue;
+        }
+        let files = resource_manager.get_assets(kind);
+        check_duplicates(&directory, &files, *kind == "shaders", &mut problems);
+        assets.insert(kind, stems(&files));
+    }
+
+    /* World::check reports missing data and world directories itself */
+    problems.extend(World::check(resource_manager).into_iter().map(Problem::World));
+
+    let mut events: Stems = Stems::new();
+    for kind in DATA_KINDS {
+        let directory = resource_manager.get_data_directory(kind);
+        if !Path::new(&directory).is_dir() {
+            continue;
+        }
+        let files = resource_manager.get_data(kind);
+        check_duplicates(&directory, &files, false, &mut problems);
+        if *kind == "events" {
+            events = stems(&files);
+        }
+    }
+
+    /* Armies, settlements and fleets are found by the name inside the file, factions by the stem */
+    let directory = resource_manager.get_world_directory("factions");
+    let factions: Option<Stems> = if Path::new(&directory).is_dir() {
+        let files = resource_manager.get_world_data("factions");
+        check_duplicates(&directory, &files, false, &mut problems);
+        Some(stems(&files))
+    } else {
+        None
+    };
+
+    for file in assets["render_units"].values().flatten() {
+        let unit: RenderUnitFile = match read_file(file) {
+            Ok(u) => u,
+            Err(e) => {
+                problems.push(e);
+                continue;
+            }
+        };
+        let references = [("animations", unit.animation), ("cameras", unit.camera), ("models", unit.model), ("projections", unit.projection), ("shaders", unit.shader), ("textures", unit.texture)];
+        for (kind, name) in references {
+            if !assets[kind].contains_key(&name) {
+                problems.push(Problem::MissingAsset { file: file.clone(), directory: resource_manager.get_asset_directory(kind), name });
+            }
+        }
+    }
+
+    let mut screens: Vec<(String, Screen)> = Vec::new();
+    for file in assets["screens"].values().flatten() {
+        match read_file::<Screen>(file) {
+            Ok(s) => screens.push((file.clone(), s)),
+            Err(e) => problems.push(e),
+        }
+    }
+
+    let mut screen_names: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (file, screen) in screens.iter() {
+        screen_names.entry(&screen.name).or_default().push(file.clone());
+    }
+    for (screen, files) in screen_names.iter().filter(|(_, f)| f.len() > 1) {
+        problems.push(Problem::DuplicateScreen { screen: screen.to_string(), files: files.clone() });
+    }
+
+    for (file, screen) in screens.iter() {
+        let missing = |kind: &str, name: &str| Problem::MissingAsset { file: file.clone(), directory: resource_manager.get_asset_directory(kind), name: name.to_string() };
+        for name in screen.get_render_units().into_iter().filter(|n| !assets["render_units"].contains_key(*n)) {
+            problems.push(missing("render_units", name));
+        }
+        for name in screen.get_texts().into_iter().filter(|n| !assets["texts"].contains_key(*n)) {
+            problems.push(missing("texts", name));
+        }
+        for name in screen.get_fonts().into_iter().filter(|n| !assets["fonts"].contains_key(*n)) {
+            problems.push(missing("fonts", name));
+        }
+
+        let (event_texts, event_textures) = screen.get_event_element_counts();
+        for code in screen.get_event_codes() {
+            let words: Vec<&str> = code.split(' ').collect();
+            match words[..] {
+                ["open", name] if !screen_names.contains_key(name) => problems.push(missing("screens", name)),
+                ["set", faction] if factions.as_ref().is_some_and(|f| !f.contains_key(faction)) => {
+                    problems.push(Problem::World(WorldError::MissingFaction { file: file.clone(), faction: faction.to_string() }))
+                }
+                ["render", "text", index] if !index.parse::<usize>().is_ok_and(|i| i < event_texts) => problems.push(Problem::MissingElement { file: file.clone(), code: code.to_string() }),
+                ["render", "texture", index] if !index.parse::<usize>().is_ok_and(|i| i < event_textures) => problems.push(Problem::MissingElement { file: file.clone(), code: code.to_string() }),
+                _ => {}
+            }
+        }
+    }
+
+    for screen in REQUIRED_SCREENS.iter().copied().chain([Outcome::Victory.get_screen(), Outcome::Defeat.get_screen()]) {
+        if !screen_names.contains_key(screen) {
+            problems.push(Problem::MissingRequired { directory: resource_manager.get_asset_directory("screens"), name: screen.to_string() });
+        }
+    }
+
+    /* Events name the texts they show, their other references are checked by World::check */
+    if !assets["texts"].contains_key(super::EVENT_CONTINUE_TEXT) {
+        problems.push(Problem::MissingRequired { directory: resource_manager.get_asset_directory("texts"), name: super::EVENT_CONTINUE_TEXT.to_string() });
+    }
+    if let Ok(rules) = World::load_rules(resource_manager) {
+        for event in rules.get_events() {
+            let file = events.get(event.get_id()).and_then(|f| f.first()).cloned().unwrap_or_default();
+            let texts = std::iter::once(event.get_text()).chain(event.get_choices().iter().map(|c| c.text.as_str()));
+            for text in texts.filter(|t| !assets["texts"].contains_key(*t)) {
+                problems.push(Problem::MissingAsset { file: file.clone(), directory: resource_manager.get_asset_directory("texts"), name: text.to_string() });
+            }
+        }
+    }
+
+    problems
+}
+
+fn stems(files: &[String]) -> Stems {
+    let mut stems = Stems::new();
+    for file in files {
+        stems.entry(world::file_stem(file)).or_default().push(file.clone());
+    }
+    stems
+}
+
+/* Only one of the files with the same stem is ever loaded. A shader is a .vs and a .fs file with
+the same stem, so shaders are compared by their whole file name. */
+fn check_duplicates(directory: &str, files: &[String], whole_name: bool, problems: &mut Vec<Problem>) {
+    let mut names: Stems = Stems::new();
+    for file in files {
+        let name = if whole_name {
+            Path::new(file).file_name().and_then(|n| n.to_str()).unwrap_or(file).to_string()
+        } else {
+            world::file_stem(file)
+        };
+        names.entry(name).or_default().push(file.clone());
+    }
+    for (stem, files) in names.into_iter().filter(|(_, f)| f.len() > 1) {
+        problems.push(Problem::DuplicateStem { directory: directory.to_string(), stem, files });
+    }
+}
+
+fn read_file<T: DeserializeOwned>(file: &str) -> Result<T, Problem> {
+    let content = match fs::read_to_string(file) {
+        Ok(c) => c,
+        Err(e) => return Err(Problem::Read { file: file.to_string(), error: e.to_string() }),
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(Problem::Parse { file: file.to_string(), error: e.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::Config;
+
+    /* Asset, data and world paths in a directory of its own with an empty directory of every asset kind */
+    struct Tree {
+        root: String,
+        resource_manager: ResourceManager,
+    }
+
+    impl Tree {
+        fn new(name: &str) -> Self {
+            let root = format!("{}/barbaria_validate_{}_{}/", std::env::temp_dir().display(), name, std::process::id());
+            let mut resource_manager = ResourceManager::new(&format!("{}assets/", root), &format!("{}config/", root), &format!("{}data/", root));
+            resource_manager.set_world(&format!("{}world/", root));
+            for kind in ASSET_KINDS {
+                fs::create_dir_all(resource_manager.get_asset_directory(kind)).unwrap();
+            }
+            Tree { root, resource_manager }
+        }
+
+        fn write(&self, file: &str, content: &str) {
+            let file = format!("{}assets/{}", self.root, file);
+            fs::create_dir_all(Path::new(&file).parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+
+        /* The tree is removed before the problems are looked at */
+        fn run(self) -> Vec<Problem> {
+            let problems = run(&self.resource_manager);
+            fs::remove_dir_all(&self.root).unwrap();
+            problems
+        }
+    }
+
+    fn screen(name: &str, event_codes: &[&str]) -> String {
+        serde_json::json!({
+            "name": name, "position": [0.0, 0.0], "scale": [1000.0, 1000.0], "background": "background",
+            "text_elements": [],
+            "texture_elements": [{"position": [0.0, 0.0], "size": [10.0, 10.0], "name": "button", "event_codes": event_codes, "gui": "None"}],
+            "event_text_elements": [],
+            "event_texture_elements": []
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn reports_bad_screen_codes() {
+        let tree = Tree::new("codes");
+        tree.write("screens/menu.json", &screen("menu", &["open nowhere", "render texture 0", "render text x", "open menu"]));
+        let problems = tree.run();
+
+        assert!(problems.iter().any(|p| matches!(p, Problem::MissingAsset { name, .. } if name == "nowhere")));
+        assert!(problems.iter().any(|p| matches!(p, Problem::MissingElement { code, .. } if code == "render texture 0")));
+        assert!(problems.iter().any(|p| matches!(p, Problem::MissingElement { code, .. } if code == "render text x")));
+        assert!(!problems.iter().any(|p| matches!(p, Problem::MissingAsset { name, .. } if name == "menu")));
+    }
+
+    #[test]
+    fn reports_duplicate_stems() {
+        let tree = Tree::new("stems");
+        tree.write("textures/banner.png", "");
+        tree.write("textures/old/banner.png", "");
+        /* A shader is meant to have both */
+        tree.write("shaders/gui.vs", "");
+        tree.write("shaders/gui.fs", "");
+        let problems = tree.run();
+
+        let duplicates: Vec<&str> = problems
+            .iter()
+            .filter_map(|p| match p {
+                Problem::DuplicateStem { stem, .. } => Some(stem.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(duplicates, vec!["banner"]);
+    }
+
+    #[test]
+    fn reports_unknown_render_unit_fields() {
+        let tree = Tree::new("render_units");
+        let unit = r#"{"animation": "a", "camera": "c", "model": "m", "projection": "p", "shader": "s", "texture": "t", "colour": "red"}"#;
+        tree.write("render_units/button.json", unit);
+        let problems = tree.run();
+
+        assert!(problems.iter().any(|p| matches!(p, Problem::Parse { file, error } if file.ends_with("button.json") && error.contains("colour"))));
+    }
+
+    #[test]
+    fn reports_missing_required_screens() {
+        let tree = Tree::new("required");
+        tree.write("screens/menu.json", &screen("main_menu", &[]));
+        let problems = tree.run();
+
+        let missing: Vec<&str> = problems
+            .iter()
+            .filter_map(|p| match p {
+                Problem::MissingRequired { name, .. } if name != super::super::EVENT_CONTINUE_TEXT => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(missing, vec!["campaign", "event", Outcome::Victory.get_screen(), Outcome::Defeat.get_screen()]);
+    }
+
+    /* main exits with 1 when validate finds a problem */
+    #[test]
+    fn validate_fails_on_problems() {
+        let tree = Tree::new("exit");
+        let valid = Game::validate(&Config { resource_manager: tree.resource_manager.clone() });
+        fs::remove_dir_all(&tree.root).unwrap();
+        assert!(!valid);
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use super::faction::Faction;
+use super::map::Map;
+
+/* One entry of the "victory" list of a faction file. A faction wins once all of its conditions are met. */
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /* Every listed region belongs to the faction */
+    HoldRegions { regions: Vec<String> },
+    /* The faction with this id has been defeated */
+    Eliminate { faction: String },
+    /* The faction is still standing when this turn ends */
+    Survive { turn: u32 },
+}
+
+impl Condition {
+    /* factions holds every faction that is still in the game */
+    pub fn is_met(&self, faction: &Faction, factions: &[&Faction], map: &Map, turn: u32) -> bool {
+        match self {
+            Condition::HoldRegions { regions } => regions.iter().all(|r| map.get_region(r).and_then(|r| r.get_owner()) == Some(faction.get_id())),
+            Condition::Eliminate { faction } => !factions.iter().any(|f| f.get_id() == faction && !is_defeated(f)),
+            Condition::Survive { turn: t } => turn >= *t,
+        }
+    }
+}
+
+/* Ends the campaign for everyone, seen from the player's side */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Victory,
+    Defeat,
+}
+
+impl Outcome {
+    pub fn get_screen(self) -> &'static str {
+        match self {
+            Outcome::Victory => "victory",
+            Outcome::Defeat => "defeat",
+        }
+    }
+}
+
+/* The global defeat rule, a faction without settlements and men under arms is out of the game.
+Armies aboard a fleet still count, empty armies do not. */
+pub fn is_defeated(faction: &Faction) -> bool {
+    let mut armies = faction.get_armies().iter().chain(faction.get_fleets().iter().flat_map(|f| f.get_armies()));
+    faction.get_settlements().is_empty() && armies.all(|a| a.get_men() == 0)
+}
+
+/* Factions without conditions can not win, they can only outlast everyone else */
+pub fn has_won(faction: &Faction, factions: &[&Faction], map: &Map, turn: u32) -> bool {
+    let conditions = faction.get_victory_conditions();
+    !conditions.is_empty() && conditions.iter().all(|c| c.is_met(faction, factions, map, turn))
+}
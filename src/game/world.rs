@@ -0,0 +1,622 @@
+use std::{collections::{BTreeMap, HashMap}, error, fmt, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::resource_manager::ResourceManager;
+
+use super::faction::{self, fleet::Fleet, army::{unit::{unit_type::UnitType, Unit}, Army}, character::{character_trait::Trait, culture::Culture, Character}, settlement::{building::Building, Settlement}, Faction};
+use super::ai;
+use super::diplomacy::{Diplomacy, State};
+use super::event::{self, Effect, Event};
+use super::map::Map;
+use super::queue::Queue;
+use super::resources::Resources;
+use super::rules::Rules;
+use super::victory::Condition;
+
+/* Layout of the files in world/factions. Armies and settlements are referenced by their name. */
+#[derive(Deserialize)]
+struct FactionFile {
+    name: String,
+    #[serde(default)]
+    culture: String,
+    #[serde(default)]
+    settlements: Vec<String>,
+    #[serde(default)]
+    armies: Vec<String>,
+    #[serde(default)]
+    fleets: Vec<String>,
+    treasury: Option<Resources>,
+    /* Diplomatic state with other factions by faction id, "vassal" means this faction serves the other */
+    #[serde(default)]
+    relations: BTreeMap<String, State>,
+    #[serde(default)]
+    victory: Vec<Condition>,
+    #[serde(default)]
+    ai: ai::Weights,
+    /* Offices left out are filled with new characters when the campaign starts */
+    leader: Option<Character>,
+    heir: Option<Character>,
+}
+
+/* Layout of the files in world/armies */
+#[derive(Deserialize)]
+struct ArmyFile {
+    name: String,
+    position: (f32, f32),
+    region: String,
+    /* Unit type ids */
+    #[serde(default)]
+    units: Vec<String>,
+    /* Unit type ids that are already paid for and waiting in the army's queue */
+    #[serde(default)]
+    recruiting_units: Vec<String>,
+    general: Option<Character>,
+}
+
+/* Layout of the files in world/fleets */
+#[derive(Deserialize)]
+struct FleetFile {
+    name: String,
+    /* A sea region or a port */
+    region: String,
+    ships: u32,
+}
+
+/* Layout of the files in world/settlements */
+#[derive(Deserialize)]
+struct SettlementFile {
+    name: String,
+    /* Building ids */
+    #[serde(default)]
+    buildings: Vec<String>,
+    #[serde(default = "default_level")]
+    level: u32,
+    /* Unit type ids */
+    #[serde(default)]
+    garrison: Vec<String>,
+    #[serde(default)]
+    population: Option<u32>,
+    /* Culture of the people, the owner's culture if it is left out */
+    #[serde(default)]
+    culture: String,
+    governor: Option<Character>,
+}
+
+fn default_level() -> u32 {
+    1
+}
+
+#[derive(Debug)]
+pub enum WorldError {
+    Read { file: String, error: String },
+    MissingDirectory { directory: String },
+    Parse { file: String, error: String },
+    MissingArmy { file: String, army: String },
+    MissingSettlement { file: String, settlement: String },
+    MissingFleet { file: String, fleet: String },
+    MissingRegion { file: String, region: String },
+    MissingLandscape { file: String, landscape: String },
+    MissingFaction { file: String, faction: String },
+    MissingBuilding { file: String, building: String },
+    MissingUnitType { file: String, unit_type: String },
+    MissingTrait { file: String, character: String, trait_id: String },
+    MissingCulture { file: String, culture: String },
+    NotNavigable { file: String, region: String },
+    /* Kind is army, settlement, fleet or faction */
+    Duplicate { kind: String, name: String, file: String },
+    EmptyName { kind: String, file: String },
+    UnknownFaction { faction: String },
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::Read { file, error } => write!(f, "Could not read {}: {}", file, error),
+            WorldError::MissingDirectory { directory } => write!(f, "The directory {} does not exist", directory),
+            WorldError::Parse { file, error } => write!(f, "Could not parse {}: {}", file, error),
+            WorldError::MissingArmy { file, army } => write!(f, "{} references the army \"{}\" which does not exist", file, army),
+            WorldError::MissingSettlement { file, settlement } => write!(f, "{} references the settlement \"{}\" which does not exist", file, settlement),
+            WorldError::MissingFleet { file, fleet } => write!(f, "{} references the fleet \"{}\" which does not exist", file, fleet),
+            WorldError::MissingRegion { file, region } => write!(f, "{} references the region \"{}\" which does not exist", file, region),
+            WorldError::MissingLandscape { file, landscape } => write!(f, "{} references the landscape \"{}\" which does not exist", file, landscape),
+            WorldError::MissingFaction { file, faction } => write!(f, "{} references the faction \"{}\" which does not exist", file, faction),
+            WorldError::MissingBuilding { file, building } => write!(f, "{} references the building \"{}\" which does not exist", file, building),
+            WorldError::MissingUnitType { file, unit_type } => write!(f, "{} references the unit type \"{}\" which does not exist", file, unit_type),
+            WorldError::MissingTrait { file, character, trait_id } => write!(f, "{} gives {} the trait \"{}\" which does not exist", file, character, trait_id),
+            WorldError::MissingCulture { file, culture } => write!(f, "{} references the culture \"{}\" which does not exist", file, culture),
+            WorldError::NotNavigable { file, region } => write!(f, "{} puts ships in the region \"{}\" which is neither sea nor a port", file, region),
+            WorldError::Duplicate { kind, name, file } => write!(f, "{} defines the {} \"{}\" which is already defined elsewhere", file, kind, name),
+            WorldError::EmptyName { kind, file } => write!(f, "{} defines a {} without a name", file, kind),
+            WorldError::UnknownFaction { faction } => write!(f, "There is no faction file for the faction \"{}\"", faction),
+        }
+    }
+}
+
+impl error::Error for WorldError {}
+
+pub struct World {
+    pub factions: Vec<Faction>,
+    pub player_faction: Faction,
+    pub map: Map,
+    pub rules: Rules,
+    pub diplomacy: Diplomacy,
+}
+
+/* Everything read from the world files. The player faction is still among the factions, the
+relations are set once it has been split off. */
+struct Parts {
+    factions: Vec<Faction>,
+    map: Map,
+    rules: Rules,
+    relations: Vec<(String, String, State)>,
+}
+
+impl World {
+    /* Loads the map, the rules and all factions of the world and resolves their army, fleet, settlement,
+    region and unit type names. The faction with the file stem player_faction_id is split off as the player faction. */
+    pub fn load(resource_manager: &ResourceManager, player_faction_id: &str) -> Result<World, WorldError> {
+        let mut problems: Vec<WorldError> = Vec::new();
+        let parts = World::read(resource_manager, &mut problems);
+        if let Some(e) = problems.into_iter().next() {
+            return Err(e);
+        }
+        let Parts { mut factions, map, rules, relations } = parts.expect("The world is read whenever there are no problems");
+
+        let player_faction = match factions.iter().position(|f| f.get_id() == player_faction_id) {
+            Some(i) => factions.remove(i),
+            None => return Err(WorldError::UnknownFaction { faction: player_faction_id.to_string() }),
+        };
+
+        let faction_ids: Vec<String> = factions.iter().chain(std::iter::once(&player_faction)).map(|f| f.get_id().to_string()).collect();
+        let mut diplomacy = Diplomacy::new(faction_ids);
+        for (id, other, state) in relations {
+            diplomacy.set_state(&id, &other, state);
+        }
+
+        Ok(World { factions, player_faction, map, rules, diplomacy })
+    }
+
+    /* Every problem of the world and rule files. Loading stops at the first one, this goes on
+    past them for the validate mode. */
+    pub fn check(resource_manager: &ResourceManager) -> Vec<WorldError> {
+        let mut problems: Vec<WorldError> = Vec::new();
+        World::read(resource_manager, &mut problems);
+        problems
+    }
+
+    /* Reads every world file, broken references are added to the problems and left out. None if
+    the map could not be read, nothing can be checked without it. */
+    fn read(resource_manager: &ResourceManager, problems: &mut Vec<WorldError>) -> Option<Parts> {
+        let map_file = resource_manager.get_map();
+        let map: Map = match read_file(&map_file) {
+            Ok(m) => m,
+            Err(e) => {
+                problems.push(e);
+                return None;
+            }
+        };
+
+        let rules = World::read_rules(resource_manager, problems);
+
+        let mut armies: HashMap<String, Army> = HashMap::new();
+        for army_file in world_files(resource_manager, "armies", problems) {
+            let a: ArmyFile = match read_file(&army_file) {
+                Ok(a) => a,
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+            if map.get_region(&a.region).is_none() {
+                problems.push(WorldError::MissingRegion { file: army_file.clone(), region: a.region.clone() });
+            }
+
+            let mut units: Vec<Unit> = Vec::new();
+            for id in a.units.iter().filter(|n| !n.is_empty()) {
+                match rules.get_unit_type(id) {
+                    Some(u) => units.push(Unit::new(u)),
+                    None => problems.push(WorldError::MissingUnitType { file: army_file.clone(), unit_type: id.clone() }),
+                }
+            }
+
+            let mut recruitment = Queue::new();
+            for id in a.recruiting_units.iter().filter(|n| !n.is_empty()) {
+                match rules.get_unit_type(id) {
+                    Some(u) => recruitment.push(id.clone(), u.get_turns(), Resources::default()),
+                    None => problems.push(WorldError::MissingUnitType { file: army_file.clone(), unit_type: id.clone() }),
+                }
+            }
+
+            check_character(&rules, &army_file, a.general.as_ref(), problems);
+            if !check_name("army", &a.name, &army_file, &armies, problems) {
+                continue;
+            }
+            let mut army = Army::new(a.name.clone(), a.position, a.region, units, recruitment);
+            *army.get_general_mut() = a.general;
+            armies.insert(a.name, army);
+        }
+
+        let mut settlements: HashMap<String, Settlement> = HashMap::new();
+        for settlement_file in world_files(resource_manager, "settlements", problems) {
+            let s: SettlementFile = match read_file(&settlement_file) {
+                Ok(s) => s,
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+
+            let mut settlement_buildings: Vec<String> = Vec::new();
+            for b in s.buildings.into_iter().filter(|n| !n.is_empty()) {
+                match rules.get_building(&b) {
+                    Some(_) => settlement_buildings.push(b),
+                    None => problems.push(WorldError::MissingBuilding { file: settlement_file.clone(), building: b }),
+                }
+            }
+
+            let mut garrison: Vec<Unit> = Vec::new();
+            for id in s.garrison.iter().filter(|n| !n.is_empty()) {
+                match rules.get_unit_type(id) {
+                    Some(u) => garrison.push(Unit::new(u)),
+                    None => problems.push(WorldError::MissingUnitType { file: settlement_file.clone(), unit_type: id.clone() }),
+                }
+            }
+            check_character(&rules, &settlement_file, s.governor.as_ref(), problems);
+            if !check_name("settlement", &s.name, &settlement_file, &settlements, problems) {
+                continue;
+            }
+            let population = s.population.unwrap_or(Settlement::default_population(s.level));
+            let mut settlement = Settlement::new(s.name.clone(), settlement_buildings, s.level, garrison, population, s.culture);
+            *settlement.get_governor_mut() = s.governor;
+            settlements.insert(s.name, settlement);
+        }
+
+        let mut fleets: HashMap<String, Fleet> = HashMap::new();
+        for fleet_file in world_files(resource_manager, "fleets", problems) {
+            let f: FleetFile = match read_file(&fleet_file) {
+                Ok(f) => f,
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+            match map.get_region(&f.region) {
+                Some(r) if map.sailing_cost(r).is_some() => {}
+                Some(_) => problems.push(WorldError::NotNavigable { file: fleet_file.clone(), region: f.region.clone() }),
+                None => problems.push(WorldError::MissingRegion { file: fleet_file.clone(), region: f.region.clone() }),
+            }
+            if !check_name("fleet", &f.name, &fleet_file, &fleets, problems) {
+                continue;
+            }
+            fleets.insert(f.name.clone(), Fleet::new(f.name, f.region, f.ships));
+        }
+
+        let mut factions: Vec<Faction> = Vec::new();
+        let mut relations: Vec<(String, String, State, String)> = Vec::new();
+        /* Faction file and the faction its victory conditions want eliminated */
+        let mut eliminations: Vec<(String, String)> = Vec::new();
+
+        for faction_file in world_files(resource_manager, "factions", problems) {
+            let f: FactionFile = match read_file(&faction_file) {
+                Ok(f) => f,
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+
+            /* Empty names are placeholders in the shipped files */
+            let mut faction_armies: Vec<Army> = Vec::new();
+            for name in f.armies.iter().filter(|n| !n.is_empty()) {
+                match armies.get(name) {
+                    Some(a) => faction_armies.push(a.clone()),
+                    None => problems.push(WorldError::MissingArmy { file: faction_file.clone(), army: name.clone() }),
+                }
+            }
+
+            let mut faction_settlements: Vec<Settlement> = Vec::new();
+            for name in f.settlements.iter().filter(|n| !n.is_empty()) {
+                match settlements.get(name) {
+                    Some(s) => {
+                        let mut s = s.clone();
+                        if s.get_culture().is_empty() {
+                            s.set_culture(f.culture.clone());
+                        }
+                        faction_settlements.push(s);
+                    }
+                    None => problems.push(WorldError::MissingSettlement { file: faction_file.clone(), settlement: name.clone() }),
+                }
+            }
+
+            let mut faction_fleets: Vec<Fleet> = Vec::new();
+            for name in f.fleets.iter().filter(|n| !n.is_empty()) {
+                match fleets.get(name) {
+                    Some(fl) => faction_fleets.push(fl.clone()),
+                    None => problems.push(WorldError::MissingFleet { file: faction_file.clone(), fleet: name.clone() }),
+                }
+            }
+
+            /* Faction files in different subdirectories may share a stem */
+            let id = file_stem(&faction_file);
+            if factions.iter().any(|other| other.get_id() == id) {
+                problems.push(WorldError::Duplicate { kind: "faction".to_string(), name: id, file: faction_file.clone() });
+                continue;
+            }
+            for (other, state) in f.relations {
+                relations.push((id.clone(), other, state, faction_file.clone()));
+            }
+            for condition in f.victory.iter() {
+                match condition {
+                    Condition::HoldRegions { regions } => {
+                        for r in regions.iter().filter(|r| map.get_region(r).is_none()) {
+                            problems.push(WorldError::MissingRegion { file: faction_file.clone(), region: r.clone() });
+                        }
+                    }
+                    Condition::Eliminate { faction } => eliminations.push((faction_file.clone(), faction.clone())),
+                    Condition::Survive { .. } => {}
+                }
+            }
+            if !f.culture.is_empty() && rules.get_culture(&f.culture).is_none() {
+                problems.push(WorldError::MissingCulture { file: faction_file.clone(), culture: f.culture.clone() });
+            }
+            check_character(&rules, &faction_file, f.leader.as_ref(), problems);
+            check_character(&rules, &faction_file, f.heir.as_ref(), problems);
+            let treasury = f.treasury.unwrap_or(faction::STARTING_TREASURY);
+            let mut faction = Faction::from_world(id, f.name, f.culture, faction_armies, faction_settlements, faction_fleets, treasury);
+            faction.set_victory_conditions(f.victory);
+            faction.set_ai_weights(f.ai);
+            faction.set_leader(f.leader, f.heir);
+            factions.push(faction);
+        }
+
+        /* Without a whole directory every name in the other files would be reported as missing */
+        if problems.iter().any(|p| matches!(p, WorldError::MissingDirectory { .. })) {
+            return None;
+        }
+
+        let faction_ids: Vec<&str> = factions.iter().map(|f| f.get_id()).collect();
+        check_map(&map, &map_file, &settlements, &faction_ids, problems);
+        /* A missing events directory has already been reported with the rules */
+        let event_files = data_files(resource_manager, "events", &mut Vec::new());
+        check_events(&rules, &map, &event_files, &faction_ids, problems);
+
+        for (file, faction) in eliminations.into_iter().filter(|(_, f)| !faction_ids.contains(&f.as_str())) {
+            problems.push(WorldError::MissingFaction { file, faction });
+        }
+
+        let mut known_relations: Vec<(String, String, State)> = Vec::new();
+        for (id, other, state, file) in relations {
+            if faction_ids.contains(&other.as_str()) {
+                known_relations.push((id, other, state));
+            } else {
+                problems.push(WorldError::MissingFaction { file, faction: other });
+            }
+        }
+
+        Some(Parts { factions, map, rules, relations: known_relations })
+    }
+
+    /* Loads the unit types, buildings, traits, cultures and events from the data path. Saved
+    campaigns only need these, the rest comes from the save. */
+    pub fn load_rules(resource_manager: &ResourceManager) -> Result<Rules, WorldError> {
+        let mut problems: Vec<WorldError> = Vec::new();
+        let rules = World::read_rules(resource_manager, &mut problems);
+        match problems.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(rules),
+        }
+    }
+
+    /* Rules from every data file that could be read, the others are added to the problems */
+    fn read_rules(resource_manager: &ResourceManager, problems: &mut Vec<WorldError>) -> Rules {
+        let unit_files = data_files(resource_manager, "units", problems);
+        let mut unit_types: Vec<UnitType> = Vec::new();
+        for unit_file in unit_files.iter() {
+            match read_file::<UnitType>(unit_file) {
+                Ok(mut u) => {
+                    u.set_id(file_stem(unit_file));
+                    unit_types.push(u);
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+
+        let building_files = data_files(resource_manager, "buildings", problems);
+        let mut buildings: Vec<Building> = Vec::new();
+        for building_file in building_files.iter() {
+            match read_file::<Building>(building_file) {
+                Ok(mut b) => {
+                    b.set_id(file_stem(building_file));
+                    buildings.push(b);
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+
+        let mut traits: Vec<Trait> = Vec::new();
+        for trait_file in data_files(resource_manager, "traits", problems) {
+            match read_file::<Trait>(&trait_file) {
+                Ok(mut t) => {
+                    t.set_id(file_stem(&trait_file));
+                    traits.push(t);
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+
+        let mut cultures: Vec<Culture> = Vec::new();
+        for culture_file in data_files(resource_manager, "cultures", problems) {
+            match read_file::<Culture>(&culture_file) {
+                Ok(mut c) => {
+                    c.set_id(file_stem(&culture_file));
+                    cultures.push(c);
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+
+        let mut events: Vec<Event> = Vec::new();
+        for event_file in data_files(resource_manager, "events", problems) {
+            match read_file::<Event>(&event_file) {
+                Ok(mut e) => {
+                    e.set_id(file_stem(&event_file));
+                    events.push(e);
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+
+        let rules = Rules::new(unit_types, buildings, traits, cultures, events);
+        check_rules(&rules, &unit_files, &building_files, problems);
+        rules
+    }
+}
+
+/* Makes sure unit types and buildings only point at unit types and buildings that exist. Files that
+could not be read have already been reported. */
+fn check_rules(rules: &Rules, unit_files: &[String], building_files: &[String], problems: &mut Vec<WorldError>) {
+    for file in unit_files {
+        let unit_type = match rules.get_unit_type(&file_stem(file)) {
+            Some(u) => u,
+            None => continue,
+        };
+        if let Some(u) = unit_type.get_upgrade().filter(|u| rules.get_unit_type(u).is_none()) {
+            problems.push(WorldError::MissingUnitType { file: file.clone(), unit_type: u.to_string() });
+        }
+        for b in unit_type.get_buildings().iter().filter(|b| rules.get_building(b).is_none()) {
+            problems.push(WorldError::MissingBuilding { file: file.clone(), building: b.clone() });
+        }
+    }
+
+    for file in building_files {
+        let building = match rules.get_building(&file_stem(file)) {
+            Some(b) => b,
+            None => continue,
+        };
+        for b in building.get_requires().iter().map(|b| b.as_str()).chain(building.get_upgrade()).filter(|b| rules.get_building(b).is_none()) {
+            problems.push(WorldError::MissingBuilding { file: file.clone(), building: b.to_string() });
+        }
+    }
+}
+
+/* Makes sure every name the map file uses points at something that was loaded */
+fn check_map(map: &Map, map_file: &str, settlements: &HashMap<String, Settlement>, faction_ids: &[&str], problems: &mut Vec<WorldError>) {
+    for region in map.get_regions() {
+        if map.get_landscape(region.get_landscape()).is_none() {
+            problems.push(WorldError::MissingLandscape { file: map_file.to_string(), landscape: region.get_landscape().to_string() });
+        }
+
+        for neighbour in region.get_land_neighbours().iter().chain(region.get_sea_neighbours()) {
+            if map.get_region(neighbour).is_none() {
+                problems.push(WorldError::MissingRegion { file: map_file.to_string(), region: neighbour.clone() });
+            }
+        }
+
+        if let Some(s) = region.get_settlement() {
+            if !settlements.contains_key(s) {
+                problems.push(WorldError::MissingSettlement { file: map_file.to_string(), settlement: s.to_string() });
+            }
+        }
+
+        if let Some(o) = region.get_owner() {
+            if !faction_ids.contains(&o) {
+                problems.push(WorldError::MissingFaction { file: map_file.to_string(), faction: o.to_string() });
+            }
+        }
+    }
+}
+
+/* Makes sure the effects of events only point at unit types, regions and factions that exist */
+fn check_events(rules: &Rules, map: &Map, event_files: &[String], faction_ids: &[&str], problems: &mut Vec<WorldError>) {
+    for file in event_files {
+        let event = match rules.get_event(&file_stem(file)) {
+            Some(e) => e,
+            None => continue,
+        };
+        for effect in event.get_effects() {
+            let (factions, region, unit_type): (Vec<&str>, Option<&str>, Option<&str>) = match effect {
+                Effect::Treasury { faction, .. } => (vec![faction], None, None),
+                Effect::Opinion { from, to, .. } => (vec![from, to], None, None),
+                Effect::Relation { faction, other, .. } => (vec![faction, other], None, None),
+                Effect::AddUnits { faction, region, unit_type, .. } => (vec![faction], Some(region), Some(unit_type)),
+                Effect::SetOwner { region, faction } => (vec![faction], Some(region), None),
+            };
+
+            for f in factions.into_iter().filter(|f| *f != event::PLAYER && !faction_ids.contains(f)) {
+                problems.push(WorldError::MissingFaction { file: file.clone(), faction: f.to_string() });
+            }
+            if let Some(r) = region.filter(|r| map.get_region(r).is_none()) {
+                problems.push(WorldError::MissingRegion { file: file.clone(), region: r.to_string() });
+            }
+            if let Some(u) = unit_type.filter(|u| rules.get_unit_type(u).is_none()) {
+                problems.push(WorldError::MissingUnitType { file: file.clone(), unit_type: u.to_string() });
+            }
+        }
+    }
+}
+
+/* Characters may only have traits that exist */
+fn check_character(rules: &Rules, file: &str, character: Option<&Character>, problems: &mut Vec<WorldError>) {
+    let character = match character {
+        Some(c) => c,
+        None => return,
+    };
+    for t in character.get_traits().iter().filter(|t| rules.get_trait(t).is_none()) {
+        problems.push(WorldError::MissingTrait { file: file.to_string(), character: character.get_name().to_string(), trait_id: t.clone() });
+    }
+}
+
+/* Faction files refer to armies, settlements and fleets by name, so every name has to be set and
+unique. Returns false if the object has to be left out, the first one of a name is kept. */
+fn check_name<T>(kind: &str, name: &str, file: &str, known: &HashMap<String, T>, problems: &mut Vec<WorldError>) -> bool {
+    if name.is_empty() {
+        problems.push(WorldError::EmptyName { kind: kind.to_string(), file: file.to_string() });
+        return false;
+    }
+    if known.contains_key(name) {
+        problems.push(WorldError::Duplicate { kind: kind.to_string(), name: name.to_string(), file: file.to_string() });
+        return false;
+    }
+    true
+}
+
+/* Files in a directory of the world path. The resource manager panics on a missing directory, here it is a problem. */
+fn world_files(resource_manager: &ResourceManager, name: &str, problems: &mut Vec<WorldError>) -> Vec<String> {
+    let directory = resource_manager.get_world_directory(name);
+    if !Path::new(&directory).is_dir() {
+        problems.push(WorldError::MissingDirectory { directory });
+        return Vec::new();
+    }
+    resource_manager.get_world_data(name)
+}
+
+/* Files in a directory of the data path, see world_files */
+fn data_files(resource_manager: &ResourceManager, name: &str, problems: &mut Vec<WorldError>) -> Vec<String> {
+    let directory = resource_manager.get_data_directory(name);
+    if !Path::new(&directory).is_dir() {
+        problems.push(WorldError::MissingDirectory { directory });
+        return Vec::new();
+    }
+    resource_manager.get_data(name)
+}
+
+fn read_file<T: DeserializeOwned>(file: &str) -> Result<T, WorldError> {
+    let content = match fs::read_to_string(file) {
+        Ok(c) => c,
+        Err(e) => return Err(WorldError::Read { file: file.to_string(), error: e.to_string() }),
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(WorldError::Parse { file: file.to_string(), error: e.to_string() }),
+    }
+}
+
+pub fn file_stem(file: &str) -> String {
+    match Path::new(file).file_stem().and_then(|s| s.to_str()) {
+        Some(s) => s.to_string(),
+        None => panic!("Could not get file name of: {}", file),
+    }
+}
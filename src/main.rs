@@ -1,4 +1,4 @@
-use std::{cell::{RefCell, RefMut}, rc::Rc, time::{Instant, SystemTime}};
+use std::{cell::{RefCell, RefMut}, env, panic::{self, AssertUnwindSafe}, process, rc::Rc, time::{Instant, SystemTime}};
 
 mod engine;
 mod game;
@@ -32,7 +32,20 @@ fn main() {
             data_path,
         ),
     };
-    program_config.resource_manager.set_world("world/");
+    program_config.resource_manager.set_world("assets/world/");
+
+    /* "validate" checks the asset, data and world files against each other without opening a window */
+    let cmd_args: Vec<String> = env::args().collect();
+    if cmd_args.get(1).map(|a| a.as_str()) == Some("validate") {
+        let valid = game::Game::validate(&program_config);
+        process::exit(if valid { 0 } else { 1 });
+    }
+
+    /* "replay <command log>" plays a recorded campaign again without opening a window */
+    if let (Some("replay"), Some(file)) = (cmd_args.get(1).map(|a| a.as_str()), cmd_args.get(2)) {
+        let matches = game::Game::replay(program_config, file);
+        process::exit(if matches { 0 } else { 1 });
+    }
 
     let mut game_engine = engine::Engine::new(&program_config);
     
@@ -45,6 +58,7 @@ fn main() {
     //game_engine.register_render_text("prince_valiant".to_string(), "testtesttesttesttesttest".to_string(), (0.9, 0.9, 0.9), (250.0, 250.0), 1.0);
 
     game.borrow_mut().open_screen("main_menu", &mut game_engine);
+    game::Game::install_panic_hook();
 
     while !game.borrow().close {
         let elapsed = start.elapsed().as_millis();
@@ -58,10 +72,20 @@ fn main() {
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
 
-        game_engine.render_tick();
-        game.borrow_mut().game_tick(&mut game_engine, &program_config);
+        /* A panic in the engine or the game still leaves an emergency save behind */
+        let tick = panic::catch_unwind(AssertUnwindSafe(|| {
+            game_engine.render_tick();
+            game.borrow_mut().game_tick(&mut game_engine, &program_config);
+        }));
+        if tick.is_err() {
+            game.borrow().write_crash_report();
+            process::exit(1);
+        }
     }
 
-    game.borrow().save_world();
+    /* A running campaign is kept so it can be loaded again */
+    if let Err(e) = game.borrow().save_game(game::EXIT_SLOT) {
+        println!("Could not save the campaign: {}", e);
+    }
     return;
 }
\ No newline at end of file
@@ -39,6 +39,11 @@ impl ResourceManager {
         return self.return_files(&shader_path);
     }
 
+    /* Directory get_assets reads, it panics if the directory is missing */
+    pub fn get_asset_directory(&self, assets_name: &str) -> String {
+        self.asset_path.to_string() + assets_name + "/"
+    }
+
     /* Functions to return data */
     pub fn get_map(&self) -> String {
         return self.data_path.to_string() + "/map.json";
@@ -49,6 +54,31 @@ impl ResourceManager {
         return self.return_files(&unit_path);
     }
 
+    /* Directory get_data reads, it panics if the directory is missing */
+    pub fn get_data_directory(&self, data_name: &str) -> String {
+        self.data_path.to_string() + data_name + "/"
+    }
+
+    /* Directory of the save slots, it is created with the first save */
+    pub fn get_saves(&self) -> String {
+        self.data_path.to_string() + "saves/"
+    }
+
+    /* Directory the command logs of campaigns are written to, see Game::replay */
+    pub fn get_replays(&self) -> String {
+        self.data_path.to_string() + "replays/"
+    }
+
+    /* Directory the crash reports are written to */
+    pub fn get_crash_reports(&self) -> String {
+        self.data_path.to_string() + "crash_reports/"
+    }
+
+    /* Directory get_world_data reads, it panics if the directory is missing */
+    pub fn get_world_directory(&self, data_name: &str) -> String {
+        self.get_world() + data_name + "/"
+    }
+
     /* Functions to return world data */
     pub fn get_world_data(&self, data_name: &str) -> Vec<String> {
         let world_data_path = match &self.world_path {
@@ -77,6 +107,8 @@ impl ResourceManager {
         };
         let mut file_names: Vec<String> = Vec::new();
         self.get_files(&mut files, &mut file_names);
+        /* read_dir order depends on the file system, sorted the world loads the same everywhere and replays match */
+        file_names.sort();
         return file_names;
     }
 